@@ -1,16 +1,128 @@
-from typing import Optional, Union
+import os
+import datetime
+from typing import AsyncIterator, Awaitable, Callable, Optional, Union, overload
+
+from batteryinfo import testing as testing
 
 class Measurement:
     value: float
     units: str
 
+    def __init__(self, value: float, units: str, decimals: int) -> None: ...
+    def formatted(self) -> str: ...
+    def to(self, units: str) -> Measurement:
+        """
+        Converts to other units of the same quantity.
+
+        Energy: Wh, mWh, kWh, J, kJ. Power: W, mW, kW. Voltage: V, mV.
+        Temperature: °C, °F, K.
+        """
+        ...
+    def __float__(self) -> float: ...
+    def __format__(self, spec: str) -> str:
+        """
+        Formats like a float followed by the units, e.g. `f"{m:.3f}"` gives "12.345 V".
+
+        The width applies to the whole string, and a trailing `~` leaves out the units.
+        """
+        ...
+    def __eq__(self, other: object) -> bool: ...
+    def __ne__(self, other: object) -> bool: ...
+    def __lt__(self, other: Union[Measurement, float]) -> bool: ...
+    def __le__(self, other: Union[Measurement, float]) -> bool: ...
+    def __gt__(self, other: Union[Measurement, float]) -> bool: ...
+    def __ge__(self, other: Union[Measurement, float]) -> bool: ...
+    def __hash__(self) -> int: ...
+    def __add__(self, other: Measurement) -> Measurement: ...
+    def __radd__(self, other: int) -> Measurement: ...
+    def __sub__(self, other: Measurement) -> Measurement: ...
+    def __mul__(self, factor: float) -> Measurement: ...
+    def __rmul__(self, factor: float) -> Measurement: ...
+    @overload
+    def __truediv__(self, other: Measurement) -> float: ...
+    @overload
+    def __truediv__(self, other: float) -> Measurement: ...
+
+class BatterySnapshot:
+    index: int
+    vendor: Optional[str]
+    model: Optional[str]
+    serial_number: Optional[str]
+    technology: BatteryTechnology
+    percent: Measurement
+    state: BatteryState
+    capacity: Measurement
+    temperature: Optional[Measurement]
+    cycle_count: Optional[int]
+    energy: Measurement
+    energy_full: Measurement
+    energy_full_design: Measurement
+    energy_rate: Measurement
+    voltage: Measurement
+    time_to_empty: Optional[Union[str, datetime.timedelta]]
+    """A `datetime.timedelta` with `TimeFormat.Timedelta`, a string otherwise."""
+    time_to_full: Optional[Union[str, datetime.timedelta]]
+    time_to_empty_seconds: Optional[float]
+    time_to_full_seconds: Optional[float]
+    timestamp: float
+    """The time the values were read, in seconds since the Unix epoch."""
+
+    def as_dict(
+        self, *, metadata: bool = False, nested: bool = False, drop_none: bool = False
+    ) -> dict[str, object]:
+        """
+        Returns all snapshot information as a dictionary.
+
+        Uses the same keys and options as `Battery.as_dict`, and always includes
+        "timestamp".
+        """
+        ...
+    def to_json(self, pretty: bool = False) -> str:
+        """
+        Returns the snapshot as JSON, following the schema from `json_schema()`.
+
+        Measurements are objects with a "value" and "units", and times are in seconds.
+        """
+        ...
+    def status(
+        self,
+        bar: StatusBar,
+        *,
+        low: float = 25.0,
+        critical: float = 10.0,
+        icons: Optional[dict[str, str]] = None,
+        classes: Optional[dict[str, str]] = None,
+        colors: Optional[dict[str, str]] = None,
+    ) -> str:
+        """
+        Renders the snapshot for a status bar.
+
+        The text shows an icon for the state, the percentage, and the time to empty or
+        to full. While not on external power, the battery is low or critical at or below
+        the `low` and `critical` percentages.
+
+        `icons` replaces icons by state ("charging", "discharging", "full",
+        "not_charging", "empty", "unknown") or level ("low", "critical"). `classes`
+        replaces the Waybar CSS classes by level (default: "warning" and "critical"), and
+        `colors` the colors by level (default: "#FFB52A" and "#FF5555").
+        """
+        ...
+
 class Battery:
+    """
+    A system battery.
+
+    A `Battery` can be shared between threads. The battery is read with the GIL
+    released, and refreshes of the same battery from different threads take turns.
+    """
+
+    index: int
     vendor: Optional[str]
     model: Optional[str]
     serial_number: Optional[str]
-    technology: str
+    technology: BatteryTechnology
     percent: Measurement
-    state: str
+    state: BatteryState
     capacity: Measurement
     temperature: Optional[Measurement]
     cycle_count: Optional[int]
@@ -19,34 +131,112 @@ class Battery:
     energy_full_design: Measurement
     energy_rate: Measurement
     voltage: Measurement
-    time_to_empty: Optional[str]
-    time_to_full: Optional[str]
+    time_to_empty: Optional[Union[str, datetime.timedelta]]
+    """A `datetime.timedelta` with `TimeFormat.Timedelta`, a string otherwise."""
+    time_to_full: Optional[Union[str, datetime.timedelta]]
+    time_to_empty_seconds: Optional[float]
+    time_to_full_seconds: Optional[float]
 
+    @property
+    def recording(self) -> Optional[str]:
+        """The path of the file being recorded to, or None if not recording."""
+        ...
     @property
     def refresh_interval(self) -> int: ...
     @refresh_interval.setter
     def refresh_interval(self, value: int) -> None: ...
+    @property
+    def temp_unit(self) -> TempUnit: ...
+    @temp_unit.setter
+    def temp_unit(self, value: TempUnit) -> None: ...
+    def temperature_in(self, unit: TempUnit) -> Optional[Measurement]:
+        """Returns the temperature in the given unit, whatever `temp_unit` is."""
+        ...
+    @property
+    def precision(self) -> dict[str, int]:
+        """The number of decimals displayed for each field shown as a Measurement."""
+        ...
+    @precision.setter
+    def precision(self, value: dict[str, int]) -> None: ...
+    @property
+    def locale(self) -> Optional[str]:
+        """The locale for displaying times and numbers, or None to use the global locale."""
+        ...
+    @locale.setter
+    def locale(self, value: Optional[str]) -> None: ...
     def __init__(
         self,
         index: int = 0,
         time_format: str = "Human",
         temp_unit: str = "DegF",
         refresh_interval: int = 500,
+        sysfs_root: Optional[Union[str, os.PathLike[str]]] = None,
+        precision: Optional[dict[str, int]] = None,
+        locale: Optional[str] = None,
     ) -> None: ...
-    def refresh(self) -> None: ...
-    def as_dict(self) -> dict[str, object]:
+    @staticmethod
+    def from_recording(
+        path: Union[str, os.PathLike[str]],
+        index: Optional[int] = None,
+        speed: Optional[float] = 1.0,
+        repeat: bool = False,
+        time_format: TimeFormat = TimeFormat.Human,
+        temp_unit: TempUnit = TempUnit.DegF,
+        refresh_interval: int = 500,
+        precision: Optional[dict[str, int]] = None,
+        locale: Optional[str] = None,
+    ) -> Battery:
+        """
+        Creates a Battery that replays a recording made with `start_recording`.
+
+        `speed` replays that many times faster than real time. With `speed=None`,
+        each refresh moves to the next record instead.
+        """
+        ...
+    def start_recording(self, path: Union[str, os.PathLike[str]], append: bool = False) -> None:
+        """
+        Starts writing the battery information to a JSON Lines file on every refresh.
+
+        A write that fails on a later refresh stops the recording and is reported through
+        `sys.unraisablehook`, without failing the refresh.
+        """
+        ...
+    def stop_recording(self) -> None: ...
+    def refresh(self, index: Optional[int] = None) -> None: ...
+    def snapshot(self) -> BatterySnapshot:
+        """
+        Returns an immutable snapshot of the battery information.
+
+        All fields of the snapshot are taken from the same refresh.
+        """
+        ...
+    def refresh_async(self) -> Awaitable[None]:
         """
-        Returns all battery information as a dictionary.
+        Refreshes the battery information without blocking the event loop.
 
-        Measurement fields are represented as tuples of (value, units).
+        Must be called from a coroutine running in an asyncio event loop.
+        """
+        ...
+    def watch(self, interval: int = 1000) -> BatteryWatch:
+        """Returns an asynchronous iterator of snapshots taken every `interval` milliseconds."""
+        ...
+    def as_dict(
+        self, *, metadata: bool = False, nested: bool = False, drop_none: bool = False
+    ) -> dict[str, object]:
+        """
+        Returns all battery information as a dictionary, refreshing first if needed.
+
+        Measurement fields are represented as tuples of (value, units), or as
+        {"value": float, "units": str} with `nested`. `metadata` adds "timestamp",
+        "refresh_interval" and "units". `drop_none` leaves out the fields that are None.
         Example:
             {
                 "vendor": Optional[str],
                 "model": Optional[str],
                 "serial_number": Optional[str],
-                "technology": str,
+                "technology": BatteryTechnology,
                 "percent": tuple[float, str],
-                "state": str,
+                "state": BatteryState,
                 "capacity": tuple[float, str],
                 "temperature": Optional[tuple[float, str]],
                 "cycle_count": Optional[int],
@@ -55,18 +245,289 @@ class Battery:
                 "energy_full_design": tuple[float, str],
                 "energy_rate": tuple[float, str],
                 "voltage": tuple[float, str],
-                "time_to_empty": Optional[str],
-                "time_to_full": Optional[str],
+                "time_to_empty": Optional[Union[str, datetime.timedelta]],
+                "time_to_full": Optional[Union[str, datetime.timedelta]],
+                "time_to_empty_seconds": Optional[float],
+                "time_to_full_seconds": Optional[float],
                 "battery_index": int,
             }
         """
         ...
+    def to_json(self, pretty: bool = False) -> str:
+        """
+        Returns all battery information as JSON, refreshing first if needed.
+
+        The JSON is the same as that of `BatterySnapshot.to_json`.
+        """
+        ...
+    def status(
+        self,
+        bar: StatusBar,
+        *,
+        low: float = 25.0,
+        critical: float = 10.0,
+        icons: Optional[dict[str, str]] = None,
+        classes: Optional[dict[str, str]] = None,
+        colors: Optional[dict[str, str]] = None,
+    ) -> str:
+        """
+        Renders the battery information for a status bar, refreshing first if needed.
+
+        The arguments are those of `BatterySnapshot.status`.
+        """
+        ...
+    def format(
+        self,
+        template: str,
+        *,
+        low: float = 25.0,
+        critical: float = 10.0,
+        icons: Optional[dict[str, str]] = None,
+    ) -> str:
+        """
+        Formats the battery information with a template, refreshing first if needed.
+
+        Fields are written in braces, e.g. "{percent} {state_icon} {time_remaining|human}",
+        and can be followed by filters and wrapped in {if ...}/{elif ...}/{else}/{end}
+        conditions. `low`, `critical` and `icons` are those of `BatterySnapshot.status`.
+        Raises ValueError if the template is not valid.
+        """
+        ...
+    @staticmethod
+    def from_dict(
+        data: dict[str, object],
+        time_format: TimeFormat = TimeFormat.Human,
+        temp_unit: TempUnit = TempUnit.DegF,
+        precision: Optional[dict[str, int]] = None,
+        locale: Optional[str] = None,
+    ) -> Battery:
+        """
+        Creates an offline Battery from a dictionary returned by `as_dict`.
+
+        The battery always reports the values from the dictionary.
+        """
+        ...
+
+class BatteryWatch(AsyncIterator[BatterySnapshot]):
+    """An asynchronous iterator of snapshots, returned by `Battery.watch()`."""
+
+    interval: int
+
+    def __aiter__(self) -> BatteryWatch: ...
+    def __anext__(self) -> Awaitable[BatterySnapshot]: ...
+
+class BatteryMonitor:
+    """
+    Watches a battery on a background thread and calls callbacks when it changes.
+
+    Callbacks are called on the monitor's thread.
+    """
+
+    running: bool
+    interval: int
+
+    def __init__(
+        self,
+        battery: Battery,
+        interval: int = 1000,
+        thresholds: list[float] = [],
+        hysteresis: float = 1.0,
+    ) -> None: ...
+    def on_state_change(self, callback: Callable[[BatteryState, BatteryState], object]) -> None:
+        """Registers `callback(old_state, new_state)`."""
+        ...
+    def on_threshold(self, callback: Callable[[float, str, float], object]) -> None:
+        """Registers `callback(threshold, direction, percent)`; direction is "below" or "above"."""
+        ...
+    def on_presence_change(self, callback: Callable[[bool], object]) -> None:
+        """Registers `callback(present)`, called when the battery is removed or plugged back in."""
+        ...
+    def start(self) -> None: ...
+    def stop(self) -> None: ...
+    def __enter__(self) -> BatteryMonitor: ...
+    def __exit__(self, exc_type: object, exc_value: object, traceback: object) -> None: ...
+
+def batteries(
+    time_format: TimeFormat = TimeFormat.Human,
+    temp_unit: TempUnit = TempUnit.DegF,
+    refresh_interval: int = 500,
+    sysfs_root: Optional[Union[str, os.PathLike[str]]] = None,
+    precision: Optional[dict[str, int]] = None,
+    locale: Optional[str] = None,
+) -> list[Battery]:
+    """
+    Returns one Battery per device reported by the system, ordered by index.
+
+    The list is empty if the system has no batteries.
+    """
+    ...
+
+def battery_count(sysfs_root: Optional[Union[str, os.PathLike[str]]] = None) -> int:
+    """Returns the number of batteries reported by the system."""
+    ...
+
+JSON_SCHEMA_VERSION: int
+"""The version of the JSON schema, written to every payload as "schema_version"."""
+
+def json_schema() -> str:
+    """Returns the JSON Schema document that `to_json` payloads validate against."""
+    ...
+
+def set_locale(locale: str) -> None:
+    """
+    Sets the global locale, used by every Battery without a locale of its own.
+
+    A code with a region, e.g. "de-AT", falls back to its language.
+    """
+    ...
+
+def get_locale() -> str:
+    """Returns the code of the global locale."""
+    ...
+
+def available_locales() -> list[str]:
+    """Returns the codes of the registered locales."""
+    ...
+
+def register_locale(
+    code: str,
+    base: str = "en",
+    decimal_separator: Optional[str] = None,
+    units: Optional[dict[str, tuple[str, str]]] = None,
+    short_units: Optional[dict[str, str]] = None,
+    separator: Optional[str] = None,
+    about: Optional[str] = None,
+    less_than_a_minute: Optional[str] = None,
+) -> None:
+    """
+    Registers a locale, starting from a copy of `base`.
+
+    Words are templates in which "{}" is replaced by the number, e.g. "{} hours".
+    The units are "day", "hour", "minute" and "second".
+    """
+    ...
+
+class MetricsServer:
+    """
+    Serves the metrics of every battery to Prometheus over HTTP, on a background thread.
+
+    Returned by `start_metrics_server`. The batteries are read on every request to /metrics.
+    """
+
+    @property
+    def host(self) -> str: ...
+    @property
+    def port(self) -> int: ...
+    @property
+    def running(self) -> bool: ...
+    def stop(self) -> None: ...
+    def __enter__(self) -> MetricsServer: ...
+    def __exit__(self, exc_type: object, exc_value: object, traceback: object) -> None: ...
+
+def prometheus_metrics(sysfs_root: Optional[Union[str, os.PathLike[str]]] = None) -> str:
+    """
+    Returns the metrics of every battery in the Prometheus text exposition format.
+
+    Every series is labelled with battery_index, vendor, model and serial_number.
+    """
+    ...
+
+def write_prometheus_textfile(
+    path: Union[str, os.PathLike[str]],
+    sysfs_root: Optional[Union[str, os.PathLike[str]]] = None,
+) -> None:
+    """
+    Writes the metrics of every battery to a file for the node_exporter textfile collector.
+
+    The file is replaced in one step, so the collector never reads a partly written file.
+    """
+    ...
+
+def start_metrics_server(
+    port: int,
+    host: str = "127.0.0.1",
+    sysfs_root: Optional[Union[str, os.PathLike[str]]] = None,
+) -> MetricsServer:
+    """
+    Starts serving the metrics of every battery over HTTP at /metrics.
+
+    Port 0 picks a free port, available as `MetricsServer.port`.
+    """
+    ...
 
 class TimeFormat:
     Seconds: str
     Minutes: str
     Human: str
+    Timedelta: str
+    HoursMinutes: str
+    Iso8601: str
+    Compact: str
+    Approximate: str
 
 class TempUnit:
     DegC: str
     DegF: str
+    Kelvin: str
+
+class StatusBar:
+    Waybar: str
+    I3blocks: str
+    I3bar: str
+    Polybar: str
+    Tmux: str
+
+class BatteryState:
+    """
+    The state of a battery.
+
+    A state compares equal to its name, e.g. `BatteryState.Charging == "Charging"`.
+    """
+
+    Charging: BatteryState
+    Discharging: BatteryState
+    Full: BatteryState
+    Empty: BatteryState
+    NotCharging: BatteryState
+    Unknown: BatteryState
+
+    @property
+    def is_charging(self) -> bool: ...
+    @property
+    def is_discharging(self) -> bool: ...
+    @property
+    def is_on_ac(self) -> bool:
+        """Whether the system is on external power: Charging, Full or NotCharging."""
+        ...
+    def __eq__(self, other: object) -> bool: ...
+    def __ne__(self, other: object) -> bool: ...
+    def __hash__(self) -> int: ...
+
+class BatteryTechnology:
+    """
+    The chemistry of a battery.
+
+    A technology compares equal to its string form, e.g. `"lithium-ion"`.
+    """
+
+    LiIon: BatteryTechnology
+    LiPo: BatteryTechnology
+    LiFePO4: BatteryTechnology
+    NiMH: BatteryTechnology
+    NiCd: BatteryTechnology
+    LeadAcid: BatteryTechnology
+    Unknown: BatteryTechnology
+
+    @property
+    def nominal_voltage(self) -> Optional[float]:
+        """The nominal voltage of a single cell in volts."""
+        ...
+    @property
+    def cycle_life(self) -> Optional[int]:
+        """The typical number of full cycles before the capacity drops to about 80%."""
+        ...
+    @property
+    def is_lithium(self) -> bool: ...
+    def __eq__(self, other: object) -> bool: ...
+    def __ne__(self, other: object) -> bool: ...
+    def __hash__(self) -> int: ...
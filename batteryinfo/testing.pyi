@@ -0,0 +1,59 @@
+"""Helpers for testing applications that use `batteryinfo` on machines without a battery."""
+
+from typing import Optional, Union
+
+from batteryinfo import Battery, BatteryState, BatteryTechnology, TempUnit, TimeFormat
+
+class FakeBattery:
+    energy: float
+    percent: float
+    state: BatteryState
+    present: bool
+
+    def __init__(
+        self,
+        vendor: Optional[str] = None,
+        model: Optional[str] = None,
+        serial_number: Optional[str] = None,
+        technology: Union[BatteryTechnology, str] = BatteryTechnology.LiIon,
+        state: Union[BatteryState, str] = "Discharging",
+        energy: float = 40.0,
+        energy_full: float = 50.0,
+        energy_full_design: float = 50.0,
+        energy_rate: float = 10.0,
+        voltage: float = 12.0,
+        temperature: Optional[float] = None,
+        cycle_count: Optional[int] = None,
+        index: int = 0,
+    ) -> None: ...
+    def battery(
+        self,
+        time_format: TimeFormat = TimeFormat.Human,
+        temp_unit: TempUnit = TempUnit.DegF,
+        refresh_interval: int = 0,
+        precision: Optional[dict[str, int]] = None,
+        locale: Optional[str] = None,
+    ) -> Battery:
+        """Returns a Battery backed by this virtual battery."""
+        ...
+    def set(
+        self,
+        vendor: Optional[str] = None,
+        model: Optional[str] = None,
+        serial_number: Optional[str] = None,
+        technology: Union[BatteryTechnology, str, None] = None,
+        state: Union[BatteryState, str, None] = None,
+        energy: Optional[float] = None,
+        energy_full: Optional[float] = None,
+        energy_full_design: Optional[float] = None,
+        energy_rate: Optional[float] = None,
+        voltage: Optional[float] = None,
+        temperature: Optional[float] = None,
+        cycle_count: Optional[int] = None,
+        present: Optional[bool] = None,
+    ) -> None:
+        """Changes the scripted values. Only the given values are changed."""
+        ...
+    def step(self, seconds: float, steps: int = 1) -> None:
+        """Advances the virtual battery by `seconds` at its current energy rate, `steps` times."""
+        ...
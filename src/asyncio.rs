@@ -0,0 +1,130 @@
+//! Refreshing batteries from asyncio applications.
+//!
+//! Reads run on a worker thread with the GIL released. The result is handed back to the event
+//! loop that started the read by completing an `asyncio.Future` with `call_soon_threadsafe`.
+
+use pyo3::prelude::*;
+use pyo3::types::{PyCFunction, PyDict, PyTuple};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::battery::Battery;
+
+/// Refreshes a battery on a worker thread and returns a future for the result.
+///
+/// # Arguments
+///
+/// * `battery` - The battery to refresh.
+/// * `delay` - How long the worker waits before reading the battery.
+/// * `snapshot` - Whether the future resolves to a `BatterySnapshot` instead of `None`.
+///
+/// # Returns
+///
+/// An `asyncio.Future` of the running event loop.
+pub fn spawn_refresh(
+    battery: &Bound<'_, Battery>,
+    delay: Duration,
+    snapshot: bool,
+) -> PyResult<PyObject> {
+    let py = battery.py();
+    let event_loop = py.import("asyncio")?.call_method0("get_running_loop")?;
+    let future = event_loop.call_method0("create_future")?;
+
+    let battery = battery.clone().unbind();
+    let (event_loop, result) = (event_loop.unbind(), future.clone().unbind());
+
+    thread::spawn(move || {
+        thread::sleep(delay);
+        let refreshed = battery.get().refresh_blocking(None, false);
+        Python::with_gil(|py| {
+            let outcome = refreshed.map_err(PyErr::from).and_then(|error| {
+                Battery::report_recording_error(py, error);
+                Ok(if snapshot {
+                    let snapshot = battery.get().take_snapshot();
+                    snapshot.into_pyobject(py)?.into_any().unbind()
+                } else {
+                    py.None()
+                })
+            });
+            complete(py, &event_loop, result, outcome);
+        });
+    });
+
+    Ok(future.unbind())
+}
+
+/// Schedules a future to be completed on its event loop.
+///
+/// Nothing happens if the future was cancelled in the meantime, or if the event loop has
+/// already been closed.
+fn complete(py: Python<'_>, event_loop: &PyObject, future: PyObject, outcome: PyResult<PyObject>) {
+    let callback = PyCFunction::new_closure(
+        py,
+        None,
+        None,
+        move |args: &Bound<'_, PyTuple>, _kwargs: Option<&Bound<'_, PyDict>>| -> PyResult<()> {
+            let py = args.py();
+            let future = future.bind(py);
+            if future.call_method0("done")?.is_truthy()? {
+                return Ok(());
+            }
+            match &outcome {
+                Ok(value) => future.call_method1("set_result", (value,))?,
+                Err(e) => future.call_method1("set_exception", (e.value(py),))?,
+            };
+            Ok(())
+        },
+    );
+    let _ = callback
+        .and_then(|callback| event_loop.call_method1(py, "call_soon_threadsafe", (callback,)));
+}
+
+/// An asynchronous iterator that refreshes a battery at a fixed interval.
+///
+/// Returned by `Battery.watch()`. Each iteration yields a `BatterySnapshot`.
+#[pyclass]
+#[derive(Debug)]
+pub struct BatteryWatch {
+    /// The battery being watched.
+    battery: Py<Battery>,
+    /// The interval between snapshots.
+    interval: Duration,
+    /// When the next snapshot is due, or `None` before the first one.
+    next: Option<Instant>,
+}
+
+impl BatteryWatch {
+    /// Creates an iterator that refreshes the battery every `interval`.
+    pub fn new(battery: Py<Battery>, interval: Duration) -> Self {
+        BatteryWatch {
+            battery,
+            interval,
+            next: None,
+        }
+    }
+}
+
+#[pymethods]
+impl BatteryWatch {
+    /// Returns the iterator itself.
+    fn __aiter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    /// Returns an awaitable for the next snapshot.
+    ///
+    /// Snapshots are spaced `interval` apart, measured from when each one was due, so a slow
+    /// consumer does not make the schedule drift.
+    fn __anext__(&mut self, py: Python<'_>) -> PyResult<PyObject> {
+        let now = Instant::now();
+        let due = self.next.map_or(now, |next| next.max(now));
+        self.next = Some(due + self.interval);
+        spawn_refresh(self.battery.bind(py), due - now, true)
+    }
+
+    /// Returns the interval between snapshots in milliseconds.
+    #[getter]
+    fn interval(&self) -> PyResult<u64> {
+        Ok(self.interval.as_millis() as u64)
+    }
+}
@@ -1,66 +1,143 @@
-use battery::Manager;
-use battery::units::{
-    electric_potential::volt,
-    energy::watt_hour,
-    power::watt,
-    ratio::percent,
-    thermodynamic_temperature::{degree_celsius, degree_fahrenheit},
-};
-use human_time::ToHumanTimeString;
 use pyo3::prelude::*;
 use pyo3::types::PyDict;
-use std::time::{Duration, Instant};
-
-use crate::enums::{TempUnit, TimeFormat};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::asyncio::{self, BatteryWatch};
+use crate::dict::{self, DictOptions};
+use crate::display::{DEFAULT_DECIMALS, DisplaySettings, MEASUREMENT_FIELDS, check_precision};
+use crate::duration::FormattedTime;
+use crate::enums::{BatteryState, BatteryTechnology, StatusBar, TempUnit, TimeFormat};
+use crate::error::{Error, Result};
+use crate::locale;
 use crate::measurement::Measurement;
+use crate::record::{Record, Recorder};
+use crate::snapshot::BatterySnapshot;
+use crate::source::{Backend, FixedSource, Reading, ReplaySource, Source};
+use crate::status::StatusStyle;
+use crate::template::Template;
+use crate::units::widen;
 
 /// Represents a system battery with properties like charge, voltage, and temperature.
-#[pyclass]
+///
+/// A `Battery` can be shared between threads. The battery is only read with the GIL released,
+/// and refreshes of the same battery from different threads take turns.
+#[pyclass(frozen)]
 #[derive(Debug)]
 pub struct Battery {
-    /// The vendor of the battery.
-    pub vendor: Option<String>,
-    /// The model of the battery.
-    pub model: Option<String>,
-    /// The serial number of the battery.
-    pub serial_number: Option<String>,
-    /// The technology of the battery.
-    pub technology: String,
-    /// The percentage of the battery that is full.
-    pub percent_full: Measurement,
-    /// The state of the battery (charging, discharging, etc.).
-    pub state: battery::State,
-    /// The capacity of the battery.
-    pub capacity: Measurement,
-    /// The temperature of the battery.
-    pub temperature: Option<Measurement>,
-    /// The cycle count of the battery.
-    pub cycle_count: Option<u32>,
-    /// The current energy of the battery.
-    pub energy: Measurement,
-    /// The full energy of the battery.
-    pub energy_full: Measurement,
-    /// The design energy of the battery.
-    pub energy_full_design: Measurement,
-    /// The energy rate of the battery.
-    pub energy_rate: Measurement,
-    /// The voltage of the battery.
-    pub voltage: Measurement,
-    /// The time to empty the battery.
-    pub time_to_empty: Option<String>,
-    /// The time to fully charge the battery.
-    pub time_to_full: Option<String>,
-    /// The format for displaying time.
-    pub time_format: TimeFormat,
-
-    /// The unit for displaying temperature.
-    pub temp_unit: TempUnit,
+    /// The source the battery information is read from.
+    ///
+    /// Only locked with the GIL released. When both locks are needed, this one is taken first.
+    source: Mutex<Box<dyn Source>>,
+    /// The cached battery information and display settings.
+    state: Mutex<State>,
+}
+
+/// The cached battery information and display settings of a `Battery`.
+#[derive(Debug)]
+struct State {
+    /// The index of the battery.
+    index: usize,
+    /// The battery information from the last refresh.
+    reading: Reading,
+    /// The settings for displaying the battery information.
+    display: DisplaySettings,
     /// The last time the battery information was refreshed.
     last_refresh: Instant,
+    /// The wall-clock time the battery information was last read.
+    captured_at: SystemTime,
     /// The interval for refreshing the battery information.
     refresh_interval: Duration,
+    /// The recorder every refresh is written to, if recording.
+    recorder: Option<Recorder>,
+}
+
+impl State {
+    /// Returns whether the battery information is newer than the refresh interval.
+    fn is_fresh(&self) -> bool {
+        self.last_refresh.elapsed() < self.refresh_interval
+    }
+
+    /// Replaces the cached battery information with a new reading, and writes it to the
+    /// recorder if recording.
+    ///
+    /// A failed write does not undo the reading. It stops the recording instead, and the error
+    /// is returned so it can be reported.
+    fn apply(&mut self, index: usize, reading: Reading) -> Option<Error> {
+        self.index = index;
+        self.reading = reading;
+        self.captured_at = SystemTime::now();
+        self.last_refresh = Instant::now();
+        let record = self.record();
+        let error = self.recorder.as_mut()?.write(&record).err();
+        if error.is_some() {
+            self.recorder = None;
+        }
+        error
+    }
+
+    /// Returns the time the battery information was last read, in seconds since the Unix epoch.
+    fn timestamp(&self) -> f64 {
+        self.captured_at
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64()
+    }
+
+    /// Returns the current battery information as a record for the recorder.
+    fn record(&self) -> Record {
+        Record {
+            timestamp: self.timestamp(),
+            index: self.index,
+            reading: self.reading.clone(),
+        }
+    }
+
+    /// Returns a snapshot of the cached battery information.
+    fn snapshot(&self) -> BatterySnapshot {
+        self.display
+            .snapshot(self.index, &self.reading, self.timestamp())
+    }
+
+    /// Returns a copy of the battery information and display settings.
+    fn cached(&self) -> Cached {
+        Cached {
+            index: self.index,
+            reading: self.reading.clone(),
+            display: self.display.clone(),
+            timestamp: self.timestamp(),
+            refresh_interval: self.refresh_interval,
+        }
+    }
+}
+
+/// A copy of the cached battery information and display settings of a `Battery`.
+///
+/// Building Python objects can run arbitrary Python code, e.g. through the garbage collector,
+/// which may switch to another thread that reads the same battery. The state is therefore
+/// copied out and unlocked before any Python object is built from it.
+#[derive(Debug)]
+struct Cached {
     /// The index of the battery.
-    battery_index: usize,
+    index: usize,
+    /// The battery information from the last refresh.
+    reading: Reading,
+    /// The settings for displaying the battery information.
+    display: DisplaySettings,
+    /// The time the battery information was read, in seconds since the Unix epoch.
+    timestamp: f64,
+    /// The interval for refreshing the battery information.
+    refresh_interval: Duration,
+}
+
+impl Cached {
+    /// Returns a snapshot of the battery information.
+    fn snapshot(&self) -> BatterySnapshot {
+        self.display
+            .snapshot(self.index, &self.reading, self.timestamp)
+    }
 }
 
 impl Battery {
@@ -68,7 +145,9 @@ impl Battery {
     ///
     /// # Arguments
     ///
+    /// * `py` - The GIL token, released while the battery is read.
     /// * `index` - The index of the battery to retrieve.
+    /// * `backend` - Where the battery information is read from.
     /// * `time_format` - The format for displaying time.
     /// * `temp_unit` - The unit for displaying temperature.
     /// * `refresh_interval` - The interval for refreshing the battery information.
@@ -77,139 +156,177 @@ impl Battery {
     ///
     /// A `Battery` instance with the retrieved information.
     fn get_battery_info(
+        py: Python<'_>,
         index: Option<usize>,
+        backend: &Backend,
         time_format: TimeFormat,
         temp_unit: TempUnit,
         refresh_interval: Duration,
     ) -> PyResult<Self> {
-        let manager = Manager::new().map_err(|e| {
-            PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!(
-                "Failed to create manager: {}",
-                e
-            ))
-        })?;
-        let batteries: Vec<_> = manager
-            .batteries()
-            .map_err(|e| {
-                PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!(
-                    "Failed to get batteries: {}",
-                    e
-                ))
-            })?
-            .collect();
-
-        if batteries.is_empty() {
-            return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
-                "No batteries found",
-            ));
-        }
+        Ok(py.allow_threads(|| {
+            let source = backend.open(index.unwrap_or(0))?;
+            Battery::open(source, time_format, temp_unit, refresh_interval)
+        })?)
+    }
 
-        let battery_index = index.unwrap_or(0);
-        if battery_index >= batteries.len() {
-            return Err(PyErr::new::<pyo3::exceptions::PyIndexError, _>(
-                "Battery index out of range",
-            ));
-        }
+    /// Retrieves every battery available through a backend.
+    ///
+    /// # Arguments
+    ///
+    /// * `py` - The GIL token, released while the batteries are read.
+    /// * `backend` - Where the battery information is read from.
+    /// * `time_format` - The format for displaying time.
+    /// * `temp_unit` - The unit for displaying temperature.
+    /// * `refresh_interval` - The interval for refreshing the battery information.
+    ///
+    /// # Returns
+    ///
+    /// One `Battery` instance per device, in index order. The list is empty if there are no
+    /// batteries.
+    pub fn all(
+        py: Python<'_>,
+        backend: &Backend,
+        time_format: TimeFormat,
+        temp_unit: TempUnit,
+        refresh_interval: Duration,
+    ) -> PyResult<Vec<Self>> {
+        Ok(py.allow_threads(|| {
+            backend
+                .open_all()?
+                .into_iter()
+                .map(|source| Battery::open(source, time_format, temp_unit, refresh_interval))
+                .collect::<Result<Vec<_>>>()
+        })?)
+    }
 
-        let battery = batteries[battery_index].as_ref().map_err(|e| {
-            PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!(
-                "Failed to get battery: {}",
-                e
-            ))
-        })?;
-
-        let vendor = battery.vendor().map(|v| v.trim().to_string());
-        let model = battery.model().map(|m| m.trim().to_string());
-        let serial_number = battery.serial_number().map(|s| s.trim().to_string());
-        let technology = format!("{}", battery.technology());
-        let percent_full = Measurement::new(
-            battery.state_of_charge().get::<percent>(),
-            "%".to_string(),
-            1,
-        );
-        let state = battery.state();
-        let capacity = Measurement::new(
-            battery.state_of_health().get::<percent>(),
-            "%".to_string(),
-            1,
-        );
-        let temperature = match temp_unit {
-            TempUnit::DegC => battery
-                .temperature()
-                .map(|t| Measurement::new(t.get::<degree_celsius>(), "°C".to_string(), 1)),
-            TempUnit::DegF => battery
-                .temperature()
-                .map(|t| Measurement::new(t.get::<degree_fahrenheit>(), "°F".to_string(), 1)),
-        };
-        let cycle_count = battery.cycle_count();
-        let energy = Measurement::new(battery.energy().get::<watt_hour>(), "Wh".to_string(), 1);
-        let energy_full = Measurement::new(
-            battery.energy_full().get::<watt_hour>(),
-            "Wh".to_string(),
-            1,
-        );
-        let energy_full_design = Measurement::new(
-            battery.energy_full_design().get::<watt_hour>(),
-            "Wh".to_string(),
-            1,
-        );
-        let energy_rate = Measurement::new(battery.energy_rate().get::<watt>(), "W".to_string(), 1);
-        let voltage = Measurement::new(battery.voltage().get::<volt>(), "V".to_string(), 1);
-
-        let time_to_empty = match time_format {
-            TimeFormat::Seconds => battery
-                .time_to_empty()
-                .map(|t| format!("{:.1} seconds", t.value)),
-            TimeFormat::Minutes => battery
-                .time_to_empty()
-                .map(|t| format!("{:.1} minutes", t.value / 60.0)),
-            TimeFormat::Human => battery
-                .time_to_empty()
-                .map(|t| Duration::from_secs_f32(t.value.trunc()).to_human_time_string()),
-        };
-        let time_to_full = match time_format {
-            TimeFormat::Seconds => battery
-                .time_to_full()
-                .map(|t| format!("{:.1} seconds", t.value)),
-            TimeFormat::Minutes => battery
-                .time_to_full()
-                .map(|t| format!("{:.1} minutes", t.value / 60.0)),
-            TimeFormat::Human => battery
-                .time_to_full()
-                .map(|t| Duration::from_secs_f32(t.value.trunc()).to_human_time_string()),
-        };
+    /// Creates a new `Battery` instance that reads from a source.
+    ///
+    /// # Arguments
+    ///
+    /// * `py` - The GIL token, released while the battery is read.
+    /// * `source` - The source to read from.
+    /// * `time_format` - The format for displaying time.
+    /// * `temp_unit` - The unit for displaying temperature.
+    /// * `refresh_interval` - The interval for refreshing the battery information.
+    pub fn from_source(
+        py: Python<'_>,
+        source: Box<dyn Source>,
+        time_format: TimeFormat,
+        temp_unit: TempUnit,
+        refresh_interval: Duration,
+    ) -> PyResult<Self> {
+        Ok(py.allow_threads(|| Battery::open(source, time_format, temp_unit, refresh_interval))?)
+    }
 
-        Ok(Battery {
-            vendor,
-            model,
-            serial_number,
-            technology,
-            percent_full,
-            state,
-            capacity,
-            temperature,
-            cycle_count,
-            energy,
-            energy_full,
-            energy_full_design,
-            energy_rate,
-            voltage,
-            time_to_empty,
-            time_to_full,
-            time_format,
-            temp_unit,
+    /// Reads a source for the first time and creates a `Battery` around it. Blocks on the
+    /// read, so call it with the GIL released.
+    fn open(
+        mut source: Box<dyn Source>,
+        time_format: TimeFormat,
+        temp_unit: TempUnit,
+        refresh_interval: Duration,
+    ) -> Result<Self> {
+        let reading = source.read()?;
+        let state = State {
+            index: source.index(),
+            reading,
+            display: DisplaySettings::new(time_format, temp_unit),
             last_refresh: Instant::now(),
+            captured_at: SystemTime::now(),
             refresh_interval,
-            battery_index,
+            recorder: None,
+        };
+        Ok(Battery {
+            source: Mutex::new(source),
+            state: Mutex::new(state),
         })
     }
 
-    fn refresh_if_needed(&mut self) -> PyResult<()> {
-        if self.last_refresh.elapsed() >= self.refresh_interval {
-            self.refresh(Some(self.battery_index))?;
-            self.last_refresh = Instant::now();
+    /// Locks a mutex, ignoring poisoning since the protected values stay consistent.
+    fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+        mutex.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Locks the cached battery information.
+    fn lock_state(&self) -> MutexGuard<'_, State> {
+        Battery::lock(&self.state)
+    }
+
+    /// Opens a separate source for the same battery, e.g. for a background thread. Blocks
+    /// while the battery is being read, so call it with the GIL released.
+    pub fn open_source(&self) -> Result<Box<dyn Source>> {
+        let source = Battery::lock(&self.source);
+        source.reopen(source.index())
+    }
+
+    /// Reads the battery and updates the cached information. Blocks on the read, so call it
+    /// with the GIL released.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - Switch to the battery at this index first (optional).
+    /// * `only_if_stale` - Skip the read if the cached information is newer than the refresh
+    ///   interval once it is this refresh's turn, e.g. because another thread refreshed it
+    ///   in the meantime.
+    ///
+    /// # Returns
+    ///
+    /// The error that stopped the recording, if the new reading could not be written to it.
+    /// The refresh took effect either way; pass the error to `report_recording_error`.
+    pub fn refresh_blocking(
+        &self,
+        index: Option<usize>,
+        only_if_stale: bool,
+    ) -> Result<Option<Error>> {
+        let mut source = Battery::lock(&self.source);
+        if only_if_stale && self.lock_state().is_fresh() {
+            return Ok(None);
         }
-        Ok(())
+        if let Some(index) = index.filter(|&index| index != source.index()) {
+            *source = source.reopen(index)?;
+        }
+        let reading = source.read()?;
+        Ok(self.lock_state().apply(source.index(), reading))
+    }
+
+    /// Reports an error that stopped the recording through `sys.unraisablehook`, since the
+    /// refresh that hit it succeeded.
+    pub fn report_recording_error(py: Python<'_>, error: Option<Error>) {
+        if let Some(error) = error {
+            PyErr::from(error).write_unraisable(py, None);
+        }
+    }
+
+    /// Refreshes the battery information if it is older than the refresh interval, and
+    /// returns a copy of it.
+    ///
+    /// The state is unlocked when this returns, so callers can build Python objects from the
+    /// copy without holding the lock.
+    fn refresh_if_needed(&self, py: Python<'_>) -> PyResult<Cached> {
+        if !self.lock_state().is_fresh() {
+            let error = py.allow_threads(|| self.refresh_blocking(None, true))?;
+            Battery::report_recording_error(py, error);
+        }
+        Ok(self.lock_state().cached())
+    }
+
+    /// Sets the number of decimals to display per field, as given to a constructor.
+    pub fn with_precision(self, precision: Option<HashMap<String, usize>>) -> PyResult<Self> {
+        if let Some(precision) = precision {
+            self.lock_state().display.precision = check_precision(precision)?;
+        }
+        Ok(self)
+    }
+
+    /// Sets the locale for displaying times and numbers, as given to a constructor.
+    pub fn with_locale(self, locale: Option<String>) -> PyResult<Self> {
+        self.set_locale(locale)?;
+        Ok(self)
+    }
+
+    /// Returns a snapshot of the cached battery information, without refreshing.
+    pub fn take_snapshot(&self) -> BatterySnapshot {
+        self.lock_state().snapshot()
     }
 }
 
@@ -223,24 +340,147 @@ impl Battery {
     /// * `time_format` - The format for displaying time (default: `TimeFormat::Human`).
     /// * `temp_unit` - The unit for displaying temperature (default: `TempUnit::DegF`).
     /// * `refresh_interval` - The interval for refreshing the battery information (default: 500 ms).
+    /// * `sysfs_root` - Read the battery from the Linux sysfs tree mounted at this path, e.g.
+    ///   `/host/sys`, instead of the platform battery API (optional).
+    /// * `precision` - The number of decimals to display per field, e.g. `{"voltage": 3}`
+    ///   (default: 1 for every field).
+    /// * `locale` - The locale for displaying times and numbers, e.g. `de` (default: the global
+    ///   locale).
     ///
     /// # Returns
     ///
     /// A `Battery` instance with the retrieved information.
     #[new]
-    #[pyo3(signature = (index=None, time_format=TimeFormat::Human, temp_unit=TempUnit::DegF, refresh_interval=500))]
+    #[pyo3(signature = (
+        index=None,
+        time_format=TimeFormat::Human,
+        temp_unit=TempUnit::DegF,
+        refresh_interval=500,
+        sysfs_root=None,
+        precision=None,
+        locale=None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
     fn new(
+        py: Python<'_>,
         index: Option<usize>,
         time_format: TimeFormat,
         temp_unit: TempUnit,
         refresh_interval: u64,
+        sysfs_root: Option<PathBuf>,
+        precision: Option<HashMap<String, usize>>,
+        locale: Option<String>,
     ) -> PyResult<Self> {
         Battery::get_battery_info(
+            py,
             index,
+            &Backend::new(sysfs_root.as_deref()),
             time_format,
             temp_unit,
             Duration::from_millis(refresh_interval),
-        )
+        )?
+        .with_precision(precision)?
+        .with_locale(locale)
+    }
+
+    /// Creates a `Battery` instance that replays a recording made with `start_recording`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The JSON Lines file to replay.
+    /// * `index` - The battery to replay, for recordings that hold several batteries (default:
+    ///   the battery of the first record).
+    /// * `speed` - How many times faster than real time to replay (default: 1.0). With `None`,
+    ///   each refresh moves to the next record instead.
+    /// * `repeat` - Whether to start over at the end of the recording (default: `False`).
+    /// * `time_format` - The format for displaying time (default: `TimeFormat::Human`).
+    /// * `temp_unit` - The unit for displaying temperature (default: `TempUnit::DegF`).
+    /// * `refresh_interval` - The interval for refreshing the battery information (default: 500 ms).
+    /// * `precision` - The number of decimals to display per field (default: 1 for every field).
+    /// * `locale` - The locale for displaying times and numbers (default: the global locale).
+    ///
+    /// # Returns
+    ///
+    /// A `Battery` instance that reads from the recording.
+    #[staticmethod]
+    #[pyo3(signature = (
+        path,
+        index=None,
+        speed=Some(1.0),
+        repeat=false,
+        time_format=TimeFormat::Human,
+        temp_unit=TempUnit::DegF,
+        refresh_interval=500,
+        precision=None,
+        locale=None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn from_recording(
+        py: Python<'_>,
+        path: PathBuf,
+        index: Option<usize>,
+        speed: Option<f64>,
+        repeat: bool,
+        time_format: TimeFormat,
+        temp_unit: TempUnit,
+        refresh_interval: u64,
+        precision: Option<HashMap<String, usize>>,
+        locale: Option<String>,
+    ) -> PyResult<Self> {
+        if speed.is_some_and(|speed| speed <= 0.0) {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "speed must be greater than 0",
+            ));
+        }
+        let source = py.allow_threads(|| ReplaySource::open(&path, index, speed, repeat))?;
+        Battery::from_source(
+            py,
+            Box::new(source),
+            time_format,
+            temp_unit,
+            Duration::from_millis(refresh_interval),
+        )?
+        .with_precision(precision)?
+        .with_locale(locale)
+    }
+
+    /// Starts writing the battery information to a JSON Lines file on every refresh.
+    ///
+    /// The current values are written immediately. Each line holds the time the values were
+    /// read and every value in Wh, W, V, °C and seconds, whatever the display settings are.
+    /// A later write that fails stops the recording, and is reported through
+    /// `sys.unraisablehook` rather than failing the refresh.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The file to write to.
+    /// * `append` - Whether to append to an existing file instead of replacing it (default:
+    ///   `False`).
+    #[pyo3(signature = (path, append=false))]
+    fn start_recording(&self, py: Python<'_>, path: PathBuf, append: bool) -> PyResult<()> {
+        Ok(py.allow_threads(|| {
+            let mut recorder = Recorder::create(&path, append)?;
+            let mut state = self.lock_state();
+            recorder.write(&state.record())?;
+            state.recorder = Some(recorder);
+            Ok::<_, Error>(())
+        })?)
+    }
+
+    /// Stops recording. Does nothing if not recording.
+    fn stop_recording(&self) -> PyResult<()> {
+        self.lock_state().recorder = None;
+        Ok(())
+    }
+
+    /// Returns the path of the file being recorded to, or `None` if not recording.
+    #[getter]
+    fn recording(&self) -> PyResult<Option<PathBuf>> {
+        Ok(self
+            .lock_state()
+            .recorder
+            .as_ref()
+            .map(|r| r.path().to_path_buf()))
     }
 
     /// Gets/sets the refresh interval.
@@ -249,133 +489,248 @@ impl Battery {
     ///
     /// * `interval_ms` - The refresh interval in milliseconds.
     #[setter]
-    fn set_refresh_interval(&mut self, interval_ms: u64) -> PyResult<()> {
-        self.refresh_interval = Duration::from_millis(interval_ms);
+    fn set_refresh_interval(&self, interval_ms: u64) -> PyResult<()> {
+        self.lock_state().refresh_interval = Duration::from_millis(interval_ms);
         Ok(())
     }
 
     /// Returns the refresh interval in milliseconds.
     #[getter]
     fn refresh_interval(&self) -> PyResult<u64> {
-        Ok(self.refresh_interval.as_millis() as u64)
+        Ok(self.lock_state().refresh_interval.as_millis() as u64)
+    }
+
+    /// Gets/sets the unit for displaying temperature.
+    ///
+    /// The new unit is used by every later read, including snapshots.
+    ///
+    /// # Arguments
+    ///
+    /// * `temp_unit` - The unit for displaying temperature.
+    #[setter]
+    fn set_temp_unit(&self, temp_unit: TempUnit) -> PyResult<()> {
+        self.lock_state().display.temp_unit = temp_unit;
+        Ok(())
+    }
+
+    /// Returns the unit for displaying temperature.
+    #[getter]
+    fn temp_unit(&self) -> PyResult<TempUnit> {
+        Ok(self.lock_state().display.temp_unit)
+    }
+
+    /// Gets/sets the number of decimals displayed for each field.
+    ///
+    /// Setting it replaces the whole configuration: fields that are left out go back to 1
+    /// decimal. Only the display is affected; `Measurement.value` always holds the value as
+    /// read, without rounding.
+    ///
+    /// # Arguments
+    ///
+    /// * `precision` - The number of decimals per field, e.g. `{"voltage": 3}`.
+    #[setter]
+    fn set_precision(&self, precision: HashMap<String, usize>) -> PyResult<()> {
+        self.lock_state().display.precision = check_precision(precision)?;
+        Ok(())
+    }
+
+    /// Returns the number of decimals displayed for each field shown as a measurement.
+    #[getter]
+    fn precision<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let precision = self.lock_state().display.precision.clone();
+        let dict = PyDict::new(py);
+        for field in MEASUREMENT_FIELDS {
+            let decimals = precision.get(field).copied();
+            dict.set_item(field, decimals.unwrap_or(DEFAULT_DECIMALS))?;
+        }
+        Ok(dict)
+    }
+
+    /// Gets/sets the locale for displaying times and numbers.
+    ///
+    /// `None` means the global locale set with `batteryinfo.set_locale`.
+    ///
+    /// # Arguments
+    ///
+    /// * `locale` - The code of a registered locale, e.g. `de`, or `None`.
+    #[setter]
+    fn set_locale(&self, locale: Option<String>) -> PyResult<()> {
+        if let Some(code) = &locale {
+            locale::find(code)?;
+        }
+        self.lock_state().display.locale = locale;
+        Ok(())
+    }
+
+    /// Returns the locale of the battery, or `None` if it uses the global locale.
+    #[getter]
+    fn locale(&self) -> PyResult<Option<String>> {
+        Ok(self.lock_state().display.locale.clone())
+    }
+
+    /// Returns the index of the battery.
+    #[getter]
+    fn index(&self) -> PyResult<usize> {
+        Ok(self.lock_state().index)
     }
 
     /// Returns the vendor of the battery.
     #[getter]
-    fn vendor(&mut self) -> PyResult<Option<String>> {
-        Ok(self.vendor.clone())
+    fn vendor(&self) -> PyResult<Option<String>> {
+        Ok(self.lock_state().reading.vendor.clone())
     }
 
     /// Returns the model of the battery.
     #[getter]
-    fn model(&mut self) -> PyResult<Option<String>> {
-        Ok(self.model.clone())
+    fn model(&self) -> PyResult<Option<String>> {
+        Ok(self.lock_state().reading.model.clone())
     }
 
     /// Returns the serial number of the battery.
     #[getter]
-    fn serial_number(&mut self) -> PyResult<Option<String>> {
-        Ok(self.serial_number.clone())
+    fn serial_number(&self) -> PyResult<Option<String>> {
+        Ok(self.lock_state().reading.serial_number.clone())
     }
 
     /// Returns the technology of the battery.
     #[getter]
-    fn technology(&mut self) -> PyResult<String> {
-        Ok(self.technology.clone())
+    fn technology(&self) -> PyResult<BatteryTechnology> {
+        Ok(self.lock_state().reading.technology)
     }
 
     /// Returns the percentage of the battery that is full.
     #[getter]
-    fn percent(&mut self) -> PyResult<Measurement> {
-        self.refresh_if_needed()?;
-        Ok(self.percent_full.clone())
+    fn percent(&self, py: Python<'_>) -> PyResult<Measurement> {
+        let state = self.refresh_if_needed(py)?;
+        Ok(state
+            .display
+            .measure("percent", state.reading.state_of_charge, "%"))
     }
 
     /// Returns the state of the battery (charging, discharging, etc.).
     #[getter]
-    fn state(&mut self) -> PyResult<String> {
-        self.refresh_if_needed()?;
-        Ok(format!("{:?}", self.state))
+    fn state(&self, py: Python<'_>) -> PyResult<BatteryState> {
+        let state = self.refresh_if_needed(py)?;
+        Ok(state.reading.state)
     }
 
     /// Returns the capacity of the battery.
     #[getter]
-    fn capacity(&mut self) -> PyResult<Measurement> {
-        self.refresh_if_needed()?;
-        Ok(self.capacity.clone())
+    fn capacity(&self, py: Python<'_>) -> PyResult<Measurement> {
+        let state = self.refresh_if_needed(py)?;
+        Ok(state
+            .display
+            .measure("capacity", state.reading.state_of_health, "%"))
     }
 
     /// Returns the temperature of the battery.
     #[getter]
-    fn temperature(&mut self) -> PyResult<Option<Measurement>> {
-        self.refresh_if_needed()?;
-        Ok(self.temperature.clone())
+    fn temperature(&self, py: Python<'_>) -> PyResult<Option<Measurement>> {
+        let state = self.refresh_if_needed(py)?;
+        Ok(state
+            .display
+            .temperature_in(&state.reading, state.display.temp_unit))
+    }
+
+    /// Returns the temperature of the battery in a given unit, whatever the configured unit is.
+    ///
+    /// # Arguments
+    ///
+    /// * `unit` - The unit to return the temperature in.
+    fn temperature_in(&self, py: Python<'_>, unit: TempUnit) -> PyResult<Option<Measurement>> {
+        let state = self.refresh_if_needed(py)?;
+        Ok(state.display.temperature_in(&state.reading, unit))
     }
 
     /// Returns the cycle count of the battery.
     #[getter]
-    fn cycle_count(&mut self) -> PyResult<Option<u32>> {
-        self.refresh_if_needed()?;
-        Ok(self.cycle_count)
+    fn cycle_count(&self, py: Python<'_>) -> PyResult<Option<u32>> {
+        let state = self.refresh_if_needed(py)?;
+        Ok(state.reading.cycle_count)
     }
 
     /// Returns the current energy of the battery.
     #[getter]
-    fn energy(&mut self) -> PyResult<Measurement> {
-        self.refresh_if_needed()?;
-        Ok(self.energy.clone())
+    fn energy(&self, py: Python<'_>) -> PyResult<Measurement> {
+        let state = self.refresh_if_needed(py)?;
+        Ok(state.display.measure("energy", state.reading.energy, "Wh"))
     }
 
     /// Returns the full energy of the battery.
     #[getter]
-    fn energy_full(&mut self) -> PyResult<Measurement> {
-        self.refresh_if_needed()?;
-        Ok(self.energy_full.clone())
+    fn energy_full(&self, py: Python<'_>) -> PyResult<Measurement> {
+        let state = self.refresh_if_needed(py)?;
+        Ok(state
+            .display
+            .measure("energy_full", state.reading.energy_full, "Wh"))
     }
 
     /// Returns the design energy of the battery.
     #[getter]
-    fn energy_full_design(&mut self) -> PyResult<Measurement> {
-        self.refresh_if_needed()?;
-        Ok(self.energy_full_design.clone())
+    fn energy_full_design(&self, py: Python<'_>) -> PyResult<Measurement> {
+        let state = self.refresh_if_needed(py)?;
+        Ok(state
+            .display
+            .measure("energy_full_design", state.reading.energy_full_design, "Wh"))
     }
 
     /// Returns the energy rate of the battery.
     #[getter]
-    fn energy_rate(&mut self) -> PyResult<Measurement> {
-        self.refresh_if_needed()?;
-        Ok(self.energy_rate.clone())
+    fn energy_rate(&self, py: Python<'_>) -> PyResult<Measurement> {
+        let state = self.refresh_if_needed(py)?;
+        Ok(state
+            .display
+            .measure("energy_rate", state.reading.energy_rate, "W"))
     }
 
     /// Returns the voltage of the battery.
     #[getter]
-    fn voltage(&mut self) -> PyResult<Measurement> {
-        self.refresh_if_needed()?;
-        Ok(self.voltage.clone())
+    fn voltage(&self, py: Python<'_>) -> PyResult<Measurement> {
+        let state = self.refresh_if_needed(py)?;
+        Ok(state.display.measure("voltage", state.reading.voltage, "V"))
     }
 
-    /// Returns the time to empty the battery.
+    /// Returns the time to empty the battery, in the configured time format.
     #[getter]
-    fn time_to_empty(&mut self) -> PyResult<Option<String>> {
-        self.refresh_if_needed()?;
-        Ok(self.time_to_empty.clone())
+    fn time_to_empty(&self, py: Python<'_>) -> PyResult<Option<FormattedTime>> {
+        let state = self.refresh_if_needed(py)?;
+        Ok(state.display.format_time(state.reading.time_to_empty))
     }
 
-    /// Returns the time to fully charge the battery.
+    /// Returns the time to fully charge the battery, in the configured time format.
     #[getter]
-    fn time_to_full(&mut self) -> PyResult<Option<String>> {
-        self.refresh_if_needed()?;
-        Ok(self.time_to_full.clone())
+    fn time_to_full(&self, py: Python<'_>) -> PyResult<Option<FormattedTime>> {
+        let state = self.refresh_if_needed(py)?;
+        Ok(state.display.format_time(state.reading.time_to_full))
     }
 
+    /// Returns the time to empty the battery in seconds, whatever the configured time format
+    /// is.
     #[getter]
-    fn hello(&mut self) -> PyResult<String> {
-        self.refresh_if_needed()?;
+    fn time_to_empty_seconds(&self, py: Python<'_>) -> PyResult<Option<f64>> {
+        let state = self.refresh_if_needed(py)?;
+        Ok(state.reading.time_to_empty.map(widen))
+    }
+
+    /// Returns the time to fully charge the battery in seconds, whatever the configured time
+    /// format is.
+    #[getter]
+    fn time_to_full_seconds(&self, py: Python<'_>) -> PyResult<Option<f64>> {
+        let state = self.refresh_if_needed(py)?;
+        Ok(state.reading.time_to_full.map(widen))
+    }
+
+    #[getter]
+    fn hello(&self, py: Python<'_>) -> PyResult<String> {
+        drop(self.refresh_if_needed(py)?);
         Ok("hello".to_string())
     }
 
     /// Refreshes the battery information.
     ///
+    /// The battery is re-read in place. The batteries are only enumerated again if a different
+    /// index is requested or the battery is no longer available.
+    ///
     /// # Arguments
     ///
     /// * `index` - The index of the battery to retrieve (optional).
@@ -384,70 +739,204 @@ impl Battery {
     ///
     /// An empty `PyResult` indicating success or failure.
     #[pyo3(signature = (index=None))]
-    fn refresh(&mut self, index: Option<usize>) -> PyResult<()> {
-        // println!("Refreshing battery information...");
-        let battery = Battery::get_battery_info(
-            index,
-            self.time_format,
-            self.temp_unit,
-            self.refresh_interval,
-        )?;
-        // Only update the fields that could possibly change.
-        self.percent_full = battery.percent_full;
-        self.state = battery.state;
-        self.capacity = battery.capacity;
-        self.temperature = battery.temperature;
-        self.cycle_count = battery.cycle_count;
-        self.energy = battery.energy;
-        self.energy_full = battery.energy_full;
-        self.energy_full_design = battery.energy_full_design;
-        self.energy_rate = battery.energy_rate;
-        self.voltage = battery.voltage;
-        self.time_to_empty = battery.time_to_empty;
-        self.time_to_full = battery.time_to_full;
+    fn refresh(&self, py: Python<'_>, index: Option<usize>) -> PyResult<()> {
+        let error = py.allow_threads(|| self.refresh_blocking(index, false))?;
+        Battery::report_recording_error(py, error);
         Ok(())
     }
 
+    /// Refreshes the battery information without blocking the event loop.
+    ///
+    /// The battery is read on a worker thread with the GIL released. Must be called from a
+    /// coroutine running in an asyncio event loop.
+    ///
+    /// # Returns
+    ///
+    /// An awaitable that completes once the battery information has been refreshed.
+    fn refresh_async(slf: &Bound<'_, Self>) -> PyResult<PyObject> {
+        asyncio::spawn_refresh(slf, Duration::ZERO, false)
+    }
+
+    /// Returns an asynchronous iterator that refreshes the battery at a fixed interval.
+    ///
+    /// Each iteration reads the battery on a worker thread with the GIL released and yields a
+    /// `BatterySnapshot`. The first snapshot is taken immediately.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - The interval between snapshots in milliseconds (default: 1000).
+    #[pyo3(signature = (interval=1000))]
+    fn watch(slf: &Bound<'_, Self>, interval: u64) -> BatteryWatch {
+        BatteryWatch::new(slf.clone().unbind(), Duration::from_millis(interval))
+    }
+
+    /// Returns an immutable snapshot of the battery information.
+    ///
+    /// The battery is refreshed first if the cached values are older than the refresh
+    /// interval, and every field of the snapshot is then taken from that same refresh.
+    fn snapshot(&self, py: Python<'_>) -> PyResult<BatterySnapshot> {
+        Ok(self.refresh_if_needed(py)?.snapshot())
+    }
+
     /// Returns all battery information as a Python dictionary.
-    fn as_dict(&self, py: Python) -> PyResult<Py<PyDict>> {
-        let dict = PyDict::new(py);
+    ///
+    /// The battery is refreshed first if the cached values are older than the refresh
+    /// interval. Missing values are `None`.
+    ///
+    /// # Arguments
+    ///
+    /// * `metadata` - Also include the `"timestamp"`, the `"refresh_interval"` in milliseconds,
+    ///   and the units of each measurement under `"units"` (default: `False`).
+    /// * `nested` - Represent measurements as `{"value": ..., "units": ...}` instead of tuples
+    ///   of `(value, units)` (default: `False`).
+    /// * `drop_none` - Leave out the fields whose value is `None` (default: `False`).
+    #[pyo3(signature = (*, metadata=false, nested=false, drop_none=false))]
+    fn as_dict<'py>(
+        &self,
+        py: Python<'py>,
+        metadata: bool,
+        nested: bool,
+        drop_none: bool,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let cached = self.refresh_if_needed(py)?;
+        let (snapshot, refresh_interval) = (cached.snapshot(), cached.refresh_interval);
+        let dict = dict::to_dict(py, &snapshot, DictOptions { nested, drop_none })?;
+        if metadata {
+            dict.set_item("timestamp", snapshot.timestamp)?;
+            dict.set_item("refresh_interval", refresh_interval.as_millis() as u64)?;
+            dict.set_item("units", dict::units(py, &snapshot)?)?;
+        }
+        Ok(dict)
+    }
 
-        dict.set_item("vendor", self.vendor.clone())?;
-        dict.set_item("model", self.model.clone())?;
-        dict.set_item("serial_number", self.serial_number.clone())?;
-        dict.set_item("technology", self.technology.clone())?;
-        dict.set_item("percent", (self.percent_full.value, self.percent_full.units.clone()))?;
-        dict.set_item("state", format!("{}", self.state))?;
-        dict.set_item("capacity", (self.capacity.value, self.capacity.units.clone()))?;
-        dict.set_item(
-            "temperature",
-            self.temperature
-                .as_ref()
-                .map(|t| (t.value, t.units.clone()))
-                .unwrap_or_default(),
-        )?;
-        dict.set_item(
-            "cycle_count",
-            self.cycle_count.map(|c| c.to_string()).unwrap_or_default(),
-        )?;
-        dict.set_item("energy", (self.energy.value, self.energy.units.clone()))?;
-        dict.set_item("energy_full", (self.energy_full.value, self.energy_full.units.clone()))?;
-        dict.set_item(
-            "energy_full_design",
-            (self.energy_full_design.value, self.energy_full_design.units.clone()),
-        )?;
-        dict.set_item("energy_rate", (self.energy_rate.value, self.energy_rate.units.clone()))?;
-        dict.set_item("voltage", (self.voltage.value, self.voltage.units.clone()))?;
-        dict.set_item(
-            "time_to_empty",
-            self.time_to_empty.clone().unwrap_or_default(),
-        )?;
-        dict.set_item(
-            "time_to_full",
-            self.time_to_full.clone().unwrap_or_default(),
+    /// Returns all battery information as JSON.
+    ///
+    /// The battery is refreshed first if the cached values are older than the refresh
+    /// interval. The JSON is the same as that of `BatterySnapshot.to_json`.
+    ///
+    /// # Arguments
+    ///
+    /// * `pretty` - Whether to indent the JSON over several lines (default: `False`).
+    #[pyo3(signature = (pretty=false))]
+    fn to_json(&self, py: Python<'_>, pretty: bool) -> PyResult<String> {
+        self.refresh_if_needed(py)?.snapshot().to_json(pretty)
+    }
+
+    /// Renders the battery information for a status bar.
+    ///
+    /// The battery is refreshed first if the cached values are older than the refresh
+    /// interval. The arguments are those of `BatterySnapshot.status`.
+    #[pyo3(signature = (bar, *, low=25.0, critical=10.0, icons=None, classes=None, colors=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn status(
+        &self,
+        py: Python<'_>,
+        bar: StatusBar,
+        low: f32,
+        critical: f32,
+        icons: Option<HashMap<String, String>>,
+        classes: Option<HashMap<String, String>>,
+        colors: Option<HashMap<String, String>>,
+    ) -> PyResult<String> {
+        let snapshot = self.refresh_if_needed(py)?.snapshot();
+        snapshot.status(bar, low, critical, icons, classes, colors)
+    }
+
+    /// Formats the battery information with a template.
+    ///
+    /// The battery is refreshed first if the cached values are older than the refresh
+    /// interval. Fields are written in braces, e.g. `"{percent} {state_icon}
+    /// {time_remaining|human}"`, and can be followed by filters and wrapped in
+    /// `{if ...}`/`{else}`/`{end}` conditions; see the README for the template language.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - The template.
+    /// * `low` - The percentage at or below which the battery is low (default: 25).
+    /// * `critical` - The percentage at or below which the battery is critical (default: 10).
+    /// * `icons` - The icons of `state_icon` to replace, by state or level name.
+    ///
+    /// # Returns
+    ///
+    /// The formatted text.
+    #[pyo3(signature = (template, *, low=25.0, critical=10.0, icons=None))]
+    fn format(
+        &self,
+        py: Python<'_>,
+        template: &str,
+        low: f32,
+        critical: f32,
+        icons: Option<HashMap<String, String>>,
+    ) -> PyResult<String> {
+        let template = Template::parse(template)?;
+        let style = StatusStyle::new(
+            low,
+            critical,
+            icons.unwrap_or_default(),
+            HashMap::new(),
+            HashMap::new(),
         )?;
-        dict.set_item("battery_index", self.battery_index)?;
+        let cached = self.refresh_if_needed(py)?;
+        let (snapshot, display) = (cached.snapshot(), cached.display);
+        Ok(template.render(&snapshot, &display, &style))
+    }
 
-        Ok(dict.into())
+    /// Creates an offline `Battery` instance from a dictionary returned by `as_dict`.
+    ///
+    /// The battery always reports the values from the dictionary. Measurements can be tuples
+    /// of `(value, units)`, `{"value": ..., "units": ...}` dictionaries or `Measurement`
+    /// objects in any units, and are converted as needed.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The dictionary to read.
+    /// * `time_format` - The format for displaying time (default: `TimeFormat::Human`).
+    /// * `temp_unit` - The unit for displaying temperature (default: `TempUnit::DegF`).
+    /// * `precision` - The number of decimals to display per field (default: 1 for every field).
+    /// * `locale` - The locale for displaying times and numbers (default: the global locale).
+    ///
+    /// # Returns
+    ///
+    /// A `Battery` instance with the values from the dictionary.
+    #[staticmethod]
+    #[pyo3(signature = (
+        data,
+        time_format=TimeFormat::Human,
+        temp_unit=TempUnit::DegF,
+        precision=None,
+        locale=None,
+    ))]
+    fn from_dict(
+        py: Python<'_>,
+        data: &Bound<'_, PyDict>,
+        time_format: TimeFormat,
+        temp_unit: TempUnit,
+        precision: Option<HashMap<String, usize>>,
+        locale: Option<String>,
+    ) -> PyResult<Self> {
+        let (index, reading, timestamp) = dict::from_dict(data)?;
+        let refresh_interval = match data.get_item("refresh_interval")? {
+            Some(interval) if !interval.is_none() => interval.extract()?,
+            _ => 500,
+        };
+        let battery = Battery::from_source(
+            py,
+            Box::new(FixedSource::new(reading, index)),
+            time_format,
+            temp_unit,
+            Duration::from_millis(refresh_interval),
+        )?
+        .with_precision(precision)?
+        .with_locale(locale)?;
+        if let Some(timestamp) = timestamp {
+            let since_epoch = Duration::try_from_secs_f64(timestamp).map_err(|_| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "Invalid timestamp: {}",
+                    timestamp
+                ))
+            })?;
+            battery.lock_state().captured_at = UNIX_EPOCH + since_epoch;
+        }
+        Ok(battery)
     }
 }
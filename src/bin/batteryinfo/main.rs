@@ -0,0 +1,491 @@
+//! The `batteryinfo` command: prints battery information as a table, JSON, `key=value` lines or
+//! Prometheus metrics, watches a battery in a live view, or renders it for a status bar,
+//! without needing Python.
+
+mod watch;
+
+use std::collections::HashMap;
+use std::env;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use batteryinfo::display::{DisplaySettings, check_precision};
+use batteryinfo::enums::{StatusBar, TempUnit, TimeFormat};
+use batteryinfo::error::Error;
+use batteryinfo::json;
+use batteryinfo::locale;
+use batteryinfo::measurement::Measurement;
+use batteryinfo::prometheus;
+use batteryinfo::snapshot::BatterySnapshot;
+use batteryinfo::source::Backend;
+use batteryinfo::status::{self, StatusStyle};
+use batteryinfo::template::Template;
+
+const USAGE: &str = "\
+Usage: batteryinfo [OPTIONS]
+       batteryinfo watch [OPTIONS]
+       batteryinfo status <BAR> [OPTIONS]
+
+Prints the information of every battery, or of one battery with --index. With watch,
+redraws the battery at --index (default: 0) in place until interrupted with Ctrl-C. With
+status, prints the battery at --index (default: 0) for a status bar: waybar, i3blocks,
+i3bar, polybar or tmux. With --template, prints one line per battery instead, e.g.
+batteryinfo --template '{percent} {state_icon} {time_remaining|human}'.
+
+Options:
+  -i, --index <N>           Show only the battery at this index
+  -t, --time-format <NAME>  Seconds, Minutes, Human, Timedelta, HoursMinutes, Iso8601,
+                            Compact or Approximate (default: Human)
+  -u, --temp-unit <NAME>    DegC, DegF or Kelvin (default: DegF)
+  -f, --format <FORMAT>     table, json, kv or prometheus (default: table)
+      --pretty              Indent JSON over several lines
+  -F, --template <TEXT>     Print each battery with a template instead of --format
+  -p, --precision <LIST>    Decimals per field, e.g. voltage=3,energy=2
+  -l, --locale <CODE>       The locale for times and numbers, e.g. de
+      --sysfs-root <PATH>   Read the Linux sysfs tree mounted at this path
+  -n, --interval <SECONDS>  With watch, the time between redraws (default: 2)
+      --low <PERCENT>       With status or --template, the low level (default: 25)
+      --critical <PERCENT>  With status or --template, the critical level (default: 10)
+      --icons <LIST>        With status or --template, icons by state or level, e.g.
+                            charging=+,low=!
+      --classes <LIST>      With status, Waybar CSS classes by level, e.g. low=warning
+      --colors <LIST>       With status, colors by level, e.g. critical=#FF0000
+  -h, --help                Print this help
+  -V, --version             Print the version
+
+Names are not case-sensitive. JSON output follows the schema of Battery.to_json(): an
+object with --index, and an array of objects otherwise.";
+
+/// How the battery information is printed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    /// Aligned labels and values, for reading.
+    Table,
+    /// JSON, in the schema of `Battery.to_json()`.
+    Json,
+    /// One `key=value` line per field, for scripts.
+    KeyValue,
+    /// Metrics in the Prometheus text exposition format.
+    Prometheus,
+}
+
+/// The parsed command-line options.
+#[derive(Debug)]
+struct Options {
+    index: Option<usize>,
+    time_format: TimeFormat,
+    temp_unit: TempUnit,
+    format: Format,
+    pretty: bool,
+    template: Option<Template>,
+    precision: HashMap<String, usize>,
+    locale: Option<String>,
+    sysfs_root: Option<PathBuf>,
+    interval: Duration,
+    style: StatusStyle,
+}
+
+/// What the command was asked to do.
+enum Command {
+    Help,
+    Version,
+    Show(Options),
+    Watch(Options),
+    Status(StatusBar, Options),
+}
+
+/// The subcommand given before the options.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Subcommand {
+    None,
+    Watch,
+    Status(StatusBar),
+}
+
+/// Parses the command-line arguments, without the program name.
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Command, String> {
+    let mut options = Options {
+        index: None,
+        time_format: TimeFormat::Human,
+        temp_unit: TempUnit::DegF,
+        format: Format::Table,
+        pretty: false,
+        template: None,
+        precision: HashMap::new(),
+        locale: None,
+        sysfs_root: None,
+        interval: Duration::from_secs(2),
+        style: StatusStyle::default(),
+    };
+    let (mut low, mut critical) = (options.style.low, options.style.critical);
+    let (mut icons, mut classes, mut colors) = (HashMap::new(), HashMap::new(), HashMap::new());
+    // The first option that only applies to status or --template, checked once all are read.
+    let mut style_flag = None;
+    let mut args = args.into_iter().peekable();
+    let subcommand = match args.next_if(|arg| arg == "watch" || arg == "status") {
+        Some(name) if name == "watch" => Subcommand::Watch,
+        Some(_) => {
+            let name = args.next().ok_or("status needs a status bar")?;
+            let bar =
+                StatusBar::find(&name).ok_or_else(|| format!("unknown status bar: {:?}", name))?;
+            Subcommand::Status(bar)
+        }
+        None => Subcommand::None,
+    };
+    let is_status = matches!(subcommand, Subcommand::Status(_));
+    while let Some(arg) = args.next() {
+        // Accept both `--flag value` and `--flag=value`.
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => {
+                (flag.to_string(), Some(value.to_string()))
+            }
+            _ => (arg, None),
+        };
+        let mut value = || {
+            inline
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("{} needs a value", flag))
+        };
+        match flag.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-V" | "--version" => return Ok(Command::Version),
+            "-i" | "--index" => {
+                let index = value()?;
+                options.index = Some(
+                    index
+                        .parse()
+                        .map_err(|_| format!("invalid battery index: {:?}", index))?,
+                );
+            }
+            "-t" | "--time-format" => {
+                let name = value()?;
+                options.time_format = TimeFormat::find(&name)
+                    .ok_or_else(|| format!("unknown time format: {:?}", name))?;
+            }
+            "-u" | "--temp-unit" => {
+                let name = value()?;
+                options.temp_unit = TempUnit::find(&name)
+                    .ok_or_else(|| format!("unknown temperature unit: {:?}", name))?;
+            }
+            "-f" | "--format" | "--pretty" | "-F" | "--template"
+                if subcommand != Subcommand::None =>
+            {
+                return Err(format!("{} cannot be used with a subcommand", flag));
+            }
+            "-f" | "--format" => {
+                options.format = match value()?.to_ascii_lowercase().as_str() {
+                    "table" => Format::Table,
+                    "json" => Format::Json,
+                    "kv" => Format::KeyValue,
+                    "prometheus" => Format::Prometheus,
+                    other => return Err(format!("unknown output format: {:?}", other)),
+                };
+            }
+            "--pretty" => options.pretty = true,
+            "-F" | "--template" => {
+                options.template = Some(Template::parse(&value()?).map_err(|e| e.to_string())?);
+            }
+            "-p" | "--precision" => options.precision = parse_precision(&value()?)?,
+            "-l" | "--locale" => {
+                let code = value()?;
+                locale::find(&code).map_err(|e| e.to_string())?;
+                options.locale = Some(code);
+            }
+            "--sysfs-root" => options.sysfs_root = Some(PathBuf::from(value()?)),
+            "-n" | "--interval" if subcommand == Subcommand::Watch => {
+                let seconds = value()?;
+                options.interval = seconds
+                    .parse()
+                    .ok()
+                    .filter(|seconds: &f64| *seconds > 0.0)
+                    .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+                    .ok_or_else(|| format!("invalid interval: {:?}", seconds))?;
+            }
+            "-n" | "--interval" => return Err(format!("{} can only be used with watch", flag)),
+            "--low" | "--critical" => {
+                let percent = value()?;
+                let percent = percent
+                    .parse()
+                    .map_err(|_| format!("invalid percentage: {:?}", percent))?;
+                if flag == "--low" {
+                    low = percent;
+                } else {
+                    critical = percent;
+                }
+                style_flag.get_or_insert(flag.clone());
+            }
+            "--icons" => {
+                icons.extend(parse_list(&value()?)?);
+                style_flag.get_or_insert(flag.clone());
+            }
+            "--classes" if is_status => classes.extend(parse_list(&value()?)?),
+            "--colors" if is_status => colors.extend(parse_list(&value()?)?),
+            "--classes" | "--colors" => {
+                return Err(format!("{} can only be used with status", flag));
+            }
+            _ => return Err(format!("unknown option: {:?}", flag)),
+        }
+    }
+    // Metrics are in base units, and reading in °C avoids rounding the temperature twice.
+    if options.format == Format::Prometheus {
+        options.temp_unit = TempUnit::DegC;
+    }
+    if let Some(flag) = style_flag.filter(|_| !is_status && options.template.is_none()) {
+        return Err(format!(
+            "{} can only be used with status or --template",
+            flag
+        ));
+    }
+    options.style =
+        StatusStyle::new(low, critical, icons, classes, colors).map_err(|e| e.to_string())?;
+    Ok(match subcommand {
+        Subcommand::None => Command::Show(options),
+        Subcommand::Watch => Command::Watch(options),
+        Subcommand::Status(bar) => Command::Status(bar, options),
+    })
+}
+
+/// Parses a list of `NAME=VALUE` items separated by commas, e.g. `voltage=3,energy=2`.
+fn parse_list(list: &str) -> Result<HashMap<String, String>, String> {
+    list.split(',')
+        .filter(|item| !item.is_empty())
+        .map(|item| {
+            let (name, value) = item
+                .split_once('=')
+                .ok_or_else(|| format!("invalid item: {:?} (expected NAME=VALUE)", item))?;
+            Ok((name.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// Parses a precision list, e.g. `voltage=3,energy=2`.
+fn parse_precision(list: &str) -> Result<HashMap<String, usize>, String> {
+    let precision = parse_list(list)?
+        .into_iter()
+        .map(|(field, decimals)| match decimals.parse() {
+            Ok(decimals) => Ok((field, decimals)),
+            Err(_) => Err(format!("invalid number of decimals: {:?}", decimals)),
+        })
+        .collect::<Result<_, String>>()?;
+    check_precision(precision).map_err(|e| e.to_string())
+}
+
+/// Returns the display settings given by the options.
+fn display_settings(options: &Options) -> DisplaySettings {
+    DisplaySettings {
+        precision: options.precision.clone(),
+        locale: options.locale.clone(),
+        ..DisplaySettings::new(options.time_format, options.temp_unit)
+    }
+}
+
+/// Returns the current time, in seconds since the Unix epoch.
+fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+/// Reads the requested batteries.
+fn read_snapshots(options: &Options) -> Result<Vec<BatterySnapshot>, Error> {
+    let backend = Backend::new(options.sysfs_root.as_deref());
+    let sources = match options.index {
+        Some(index) => vec![backend.open(index)?],
+        None => backend.open_all()?,
+    };
+    if sources.is_empty() {
+        return Err(Error::NoBatteries);
+    }
+    let display = display_settings(options);
+    sources
+        .into_iter()
+        .map(|mut source| {
+            let reading = source.read()?;
+            Ok(display.snapshot(source.index(), &reading, now()))
+        })
+        .collect()
+}
+
+/// Returns the key, label and value of each field of a snapshot.
+///
+/// Measurements include their units when `units` is set. Missing values are `None`.
+fn fields(
+    snapshot: &BatterySnapshot,
+    units: bool,
+) -> Vec<(&'static str, &'static str, Option<String>)> {
+    let measurement = |m: &Measurement| {
+        if units {
+            m.to_string()
+        } else {
+            m.format_value()
+        }
+    };
+    vec![
+        ("battery_index", "Battery", Some(snapshot.index.to_string())),
+        ("vendor", "Vendor", snapshot.vendor.clone()),
+        ("model", "Model", snapshot.model.clone()),
+        (
+            "serial_number",
+            "Serial number",
+            snapshot.serial_number.clone(),
+        ),
+        (
+            "technology",
+            "Technology",
+            Some(snapshot.technology.name().to_string()),
+        ),
+        ("state", "State", Some(snapshot.state.name().to_string())),
+        ("percent", "Percent", Some(measurement(&snapshot.percent))),
+        (
+            "capacity",
+            "Capacity",
+            Some(measurement(&snapshot.capacity)),
+        ),
+        (
+            "temperature",
+            "Temperature",
+            snapshot.temperature.as_ref().map(measurement),
+        ),
+        (
+            "cycle_count",
+            "Cycle count",
+            snapshot.cycle_count.map(|c| c.to_string()),
+        ),
+        ("energy", "Energy", Some(measurement(&snapshot.energy))),
+        (
+            "energy_full",
+            "Energy full",
+            Some(measurement(&snapshot.energy_full)),
+        ),
+        (
+            "energy_full_design",
+            "Energy full design",
+            Some(measurement(&snapshot.energy_full_design)),
+        ),
+        (
+            "energy_rate",
+            "Energy rate",
+            Some(measurement(&snapshot.energy_rate)),
+        ),
+        ("voltage", "Voltage", Some(measurement(&snapshot.voltage))),
+        (
+            "time_to_empty",
+            "Time to empty",
+            snapshot.time_to_empty.as_ref().map(|t| t.to_string()),
+        ),
+        (
+            "time_to_full",
+            "Time to full",
+            snapshot.time_to_full.as_ref().map(|t| t.to_string()),
+        ),
+    ]
+}
+
+/// Formats snapshots as aligned labels and values, with a blank line between batteries.
+fn table(snapshots: &[BatterySnapshot]) -> String {
+    let tables: Vec<String> = snapshots
+        .iter()
+        .map(|snapshot| {
+            let fields = fields(snapshot, true);
+            let width = fields
+                .iter()
+                .map(|(_, label, _)| label.len())
+                .max()
+                .unwrap_or(0);
+            fields
+                .into_iter()
+                .map(|(_, label, value)| {
+                    let value = value.unwrap_or_else(|| "-".to_string());
+                    format!("{:width$}  {}\n", label, value, width = width)
+                })
+                .collect()
+        })
+        .collect();
+    tables.join("\n")
+}
+
+/// Formats snapshots as `key=value` lines, with measurements in the units given by the options
+/// and missing values left empty. Batteries are separated by a blank line.
+fn key_values(snapshots: &[BatterySnapshot]) -> String {
+    let blocks: Vec<String> = snapshots
+        .iter()
+        .map(|snapshot| {
+            let mut lines: String = fields(snapshot, false)
+                .into_iter()
+                .map(|(key, _, value)| format!("{}={}\n", key, value.unwrap_or_default()))
+                .collect();
+            for (key, seconds) in [
+                ("time_to_empty_seconds", snapshot.time_to_empty_seconds),
+                ("time_to_full_seconds", snapshot.time_to_full_seconds),
+            ] {
+                let seconds = seconds.map(|s| s.to_string()).unwrap_or_default();
+                lines += &format!("{}={}\n", key, seconds);
+            }
+            lines
+        })
+        .collect();
+    blocks.join("\n")
+}
+
+/// Prints the requested batteries in the requested format, or with the template.
+fn show(options: &Options) -> Result<(), Error> {
+    let snapshots = read_snapshots(options)?;
+    if let Some(template) = &options.template {
+        let display = display_settings(options);
+        for snapshot in &snapshots {
+            println!("{}", template.render(snapshot, &display, &options.style));
+        }
+        return Ok(());
+    }
+    match options.format {
+        Format::Table => print!("{}", table(&snapshots)),
+        Format::KeyValue => print!("{}", key_values(&snapshots)),
+        Format::Prometheus => print!("{}", prometheus::render(&snapshots)),
+        Format::Json if options.index.is_some() => {
+            println!("{}", json::to_string(&snapshots[0], options.pretty)?)
+        }
+        Format::Json => println!("{}", json::to_string(&snapshots, options.pretty)?),
+    }
+    Ok(())
+}
+
+/// Prints the battery at the requested index, or the first one, for a status bar.
+fn show_status(bar: StatusBar, options: &Options) -> Result<(), Error> {
+    let index = options.index.unwrap_or(0);
+    let reading = Backend::new(options.sysfs_root.as_deref())
+        .open(index)?
+        .read()?;
+    let snapshot = display_settings(options).snapshot(index, &reading, now());
+    println!("{}", status::render(bar, &snapshot, &options.style));
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let result = match parse_args(env::args().skip(1)) {
+        Ok(Command::Help) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Ok(Command::Version) => {
+            println!("batteryinfo {}", env!("CARGO_PKG_VERSION"));
+            return ExitCode::SUCCESS;
+        }
+        Ok(Command::Show(options)) => show(&options),
+        Ok(Command::Watch(options)) => watch::run(&options),
+        Ok(Command::Status(bar, options)) => show_status(bar, &options),
+        Err(message) => {
+            eprintln!("batteryinfo: {}\n\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("batteryinfo: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,175 @@
+//! `batteryinfo watch`: a view of one battery that is redrawn in place.
+
+use std::collections::VecDeque;
+use std::env;
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
+use std::thread;
+
+use batteryinfo::enums::BatteryState;
+use batteryinfo::error::{Error, Result};
+use batteryinfo::measurement::Measurement;
+use batteryinfo::snapshot::BatterySnapshot;
+use batteryinfo::source::Backend;
+
+use crate::{Options, display_settings, now};
+
+/// The number of characters in the charge bar.
+const BAR_WIDTH: usize = 30;
+
+/// The number of energy rate samples kept for the sparkline.
+const HISTORY: usize = 60;
+
+/// The bars of the sparkline, lowest first.
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Moves the cursor to the top left corner.
+const HOME: &str = "\x1b[H";
+/// Clears the rest of the line.
+const CLEAR_LINE: &str = "\x1b[K";
+/// Clears everything below the cursor.
+const CLEAR_BELOW: &str = "\x1b[J";
+
+/// Reads the battery at the interval and redraws it, until the process is interrupted.
+///
+/// The view is drawn over the previous one instead of clearing the screen, so it does not
+/// flicker, and nothing needs restoring when the process is interrupted.
+pub fn run(options: &Options) -> Result<()> {
+    let index = options.index.unwrap_or(0);
+    let mut source = Backend::new(options.sysfs_root.as_deref()).open(index)?;
+    let display = display_settings(options);
+    let color = io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none();
+    let mut rates = VecDeque::with_capacity(HISTORY);
+    let mut stdout = io::stdout();
+    write_screen(&mut stdout, "\x1b[2J")?;
+    loop {
+        let reading = source.read()?;
+        if rates.len() == HISTORY {
+            rates.pop_front();
+        }
+        rates.push_back(reading.energy_rate);
+        let snapshot = display.snapshot(index, &reading, now());
+        let view = render(&snapshot, &rates, options, color);
+        let lines: String = view
+            .lines()
+            .map(|line| format!("{}{}\n", line, CLEAR_LINE))
+            .collect();
+        write_screen(&mut stdout, &format!("{}{}{}", HOME, lines, CLEAR_BELOW))?;
+        thread::sleep(options.interval);
+    }
+}
+
+/// Writes to the terminal and flushes it.
+fn write_screen(stdout: &mut io::Stdout, text: &str) -> Result<()> {
+    stdout
+        .write_all(text.as_bytes())
+        .and_then(|()| stdout.flush())
+        .map_err(|e| Error::Io(PathBuf::from("standard output"), e))
+}
+
+/// Renders the view of a battery.
+///
+/// # Arguments
+///
+/// * `snapshot` - The current battery information.
+/// * `rates` - The recent energy rates in watts, oldest first.
+/// * `options` - The command-line options.
+/// * `color` - Whether to color the charge bar by level.
+fn render(
+    snapshot: &BatterySnapshot,
+    rates: &VecDeque<f32>,
+    options: &Options,
+    color: bool,
+) -> String {
+    let name: Vec<&str> = [&snapshot.vendor, &snapshot.model]
+        .into_iter()
+        .flatten()
+        .map(String::as_str)
+        .collect();
+    let remaining = match (&snapshot.time_to_empty, &snapshot.time_to_full) {
+        (Some(time), _) => format!("{} to empty", time),
+        (None, Some(time)) => format!("{} to full", time),
+        (None, None) => "-".to_string(),
+    };
+    let range = |value: f32| Measurement {
+        value,
+        ..snapshot.energy_rate.clone()
+    };
+    let (low, high) = bounds(rates);
+    let mut view = format!("Battery {}  {}\n\n", snapshot.index, name.join(" "));
+    view += &format!(
+        "Charge     {}  {}\n",
+        charge_bar(snapshot.percent.value, color),
+        snapshot.percent
+    );
+    view += &format!("State      {}\n", state_text(snapshot.state));
+    view += &format!("Rate       {}\n", snapshot.energy_rate);
+    view += &format!(
+        "History    {}  {} – {}\n",
+        sparkline(rates),
+        range(low).format_value(),
+        range(high)
+    );
+    view += &format!("Remaining  {}\n\n", remaining);
+    view += &format!(
+        "Every {}s, last {} samples. Press Ctrl-C to quit.\n",
+        options.interval.as_secs_f64(),
+        HISTORY
+    );
+    view
+}
+
+/// Returns the state with an arrow for its direction.
+fn state_text(state: BatteryState) -> String {
+    match state {
+        BatteryState::Charging => "⇡ Charging".to_string(),
+        BatteryState::Discharging => "⇣ Discharging".to_string(),
+        BatteryState::Full => "✓ Full".to_string(),
+        state => state.name().to_string(),
+    }
+}
+
+/// Draws a bar that is filled in proportion to the percentage.
+///
+/// With `color`, the bar is red at 10% or below, yellow at 25% or below, and green above.
+fn charge_bar(percent: f32, color: bool) -> String {
+    let filled = ((percent.clamp(0.0, 100.0) / 100.0) * BAR_WIDTH as f32).round() as usize;
+    let bar = format!("{}{}", "█".repeat(filled), "░".repeat(BAR_WIDTH - filled));
+    if !color {
+        return format!("[{}]", bar);
+    }
+    let code = match percent {
+        p if p <= 10.0 => 31,
+        p if p <= 25.0 => 33,
+        _ => 32,
+    };
+    format!("[\x1b[{}m{}\x1b[0m]", code, bar)
+}
+
+/// Returns the lowest and the highest value.
+fn bounds(values: &VecDeque<f32>) -> (f32, f32) {
+    values
+        .iter()
+        .fold((f32::MAX, f32::MIN), |(low, high), &value| {
+            (low.min(value), high.max(value))
+        })
+}
+
+/// Draws the values as a sparkline, scaled between the lowest and the highest value.
+///
+/// When every value is the same, the line is drawn at the lowest level.
+fn sparkline(values: &VecDeque<f32>) -> String {
+    let (low, high) = bounds(values);
+    let top = (SPARKS.len() - 1) as f32;
+    values
+        .iter()
+        .map(|&value| {
+            let level = if high > low {
+                ((value - low) / (high - low) * top).round() as usize
+            } else {
+                0
+            };
+            SPARKS[level]
+        })
+        .collect()
+}
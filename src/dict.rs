@@ -0,0 +1,206 @@
+//! Converting battery information to and from Python dictionaries.
+
+use pyo3::IntoPyObjectExt;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::enums::{BatteryState, BatteryTechnology, Named};
+use crate::measurement::Measurement;
+use crate::snapshot::BatterySnapshot;
+use crate::source::{Reading, parse_state, parse_technology};
+use crate::units::convert;
+
+/// How measurements and missing values are represented in a dictionary.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DictOptions {
+    /// Represent measurements as `{"value": ..., "units": ...}` instead of `(value, units)`.
+    pub nested: bool,
+    /// Leave out the fields whose value is `None`.
+    pub drop_none: bool,
+}
+
+/// Returns the fields of a snapshot shown as measurements, with their names.
+fn measurements(snapshot: &BatterySnapshot) -> [(&'static str, Option<&Measurement>); 8] {
+    [
+        ("percent", Some(&snapshot.percent)),
+        ("capacity", Some(&snapshot.capacity)),
+        ("temperature", snapshot.temperature.as_ref()),
+        ("energy", Some(&snapshot.energy)),
+        ("energy_full", Some(&snapshot.energy_full)),
+        ("energy_full_design", Some(&snapshot.energy_full_design)),
+        ("energy_rate", Some(&snapshot.energy_rate)),
+        ("voltage", Some(&snapshot.voltage)),
+    ]
+}
+
+/// Converts a snapshot to a dictionary, without the timestamp.
+///
+/// Measurements are tuples of `(value, units)`, or dictionaries with `nested`. Missing values
+/// are `None`, or left out with `drop_none`.
+pub fn to_dict<'py>(
+    py: Python<'py>,
+    snapshot: &BatterySnapshot,
+    options: DictOptions,
+) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    let set = |key: &str, value: Bound<'py, PyAny>| {
+        if options.drop_none && value.is_none() {
+            return Ok(());
+        }
+        dict.set_item(key, value)
+    };
+    let measurement = |measurement: Option<&Measurement>| match measurement {
+        None => Ok(py.None().into_bound(py)),
+        Some(m) if options.nested => {
+            let nested = PyDict::new(py);
+            nested.set_item("value", m.raw_value())?;
+            nested.set_item("units", &m.units)?;
+            Ok(nested.into_any())
+        }
+        Some(m) => (m.raw_value(), &m.units).into_bound_py_any(py),
+    };
+
+    set("vendor", snapshot.vendor.clone().into_bound_py_any(py)?)?;
+    set("model", snapshot.model.clone().into_bound_py_any(py)?)?;
+    set(
+        "serial_number",
+        snapshot.serial_number.clone().into_bound_py_any(py)?,
+    )?;
+    set("technology", snapshot.technology.into_bound_py_any(py)?)?;
+    set("percent", measurement(Some(&snapshot.percent))?)?;
+    set("state", snapshot.state.into_bound_py_any(py)?)?;
+    set("capacity", measurement(Some(&snapshot.capacity))?)?;
+    set("temperature", measurement(snapshot.temperature.as_ref())?)?;
+    set("cycle_count", snapshot.cycle_count.into_bound_py_any(py)?)?;
+    set("energy", measurement(Some(&snapshot.energy))?)?;
+    set("energy_full", measurement(Some(&snapshot.energy_full))?)?;
+    set(
+        "energy_full_design",
+        measurement(Some(&snapshot.energy_full_design))?,
+    )?;
+    set("energy_rate", measurement(Some(&snapshot.energy_rate))?)?;
+    set("voltage", measurement(Some(&snapshot.voltage))?)?;
+    set(
+        "time_to_empty",
+        snapshot.time_to_empty.clone().into_bound_py_any(py)?,
+    )?;
+    set(
+        "time_to_full",
+        snapshot.time_to_full.clone().into_bound_py_any(py)?,
+    )?;
+    set(
+        "time_to_empty_seconds",
+        snapshot.time_to_empty_seconds.into_bound_py_any(py)?,
+    )?;
+    set(
+        "time_to_full_seconds",
+        snapshot.time_to_full_seconds.into_bound_py_any(py)?,
+    )?;
+    set("battery_index", snapshot.index.into_bound_py_any(py)?)?;
+    Ok(dict)
+}
+
+/// Returns the units of each field of a snapshot shown as a measurement.
+///
+/// The temperature is left out if the battery does not report one.
+pub fn units<'py>(py: Python<'py>, snapshot: &BatterySnapshot) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    for (name, measurement) in measurements(snapshot) {
+        if let Some(measurement) = measurement {
+            dict.set_item(name, &measurement.units)?;
+        }
+    }
+    Ok(dict)
+}
+
+/// Reads a measurement from a dictionary and converts it to the units used by `Reading`.
+///
+/// The measurement can be a `Measurement`, a `(value, units)` tuple, a
+/// `{"value": ..., "units": ...}` dictionary, or a number already in `base_units`.
+fn measurement_from(value: &Bound<'_, PyAny>, field: &str, base_units: &str) -> PyResult<f32> {
+    let (value, units): (f64, String) = if let Ok(m) = value.downcast::<Measurement>() {
+        let m = m.borrow();
+        (m.raw_value(), m.units.clone())
+    } else if let Ok(nested) = value.downcast::<PyDict>() {
+        let item = |key: &str| {
+            nested
+                .get_item(key)?
+                .ok_or_else(|| PyValueError::new_err(format!("{:?} has no {:?}", field, key)))
+        };
+        (item("value")?.extract()?, item("units")?.extract()?)
+    } else if let Ok(pair) = value.extract::<(f64, String)>() {
+        pair
+    } else {
+        (value.extract()?, base_units.to_string())
+    };
+    Ok(convert(value, &units, base_units)? as f32)
+}
+
+/// Reads a time in seconds from a dictionary.
+///
+/// The `<field>_seconds` key is used if present. Otherwise the field itself is used if it is a
+/// `datetime.timedelta` or a number of seconds; times formatted as text are ignored.
+fn seconds_from(data: &Bound<'_, PyDict>, field: &str) -> PyResult<Option<f32>> {
+    if let Some(seconds) = data.get_item(format!("{}_seconds", field))? {
+        return seconds.extract();
+    }
+    let Some(value) = data.get_item(field)? else {
+        return Ok(None);
+    };
+    let timedelta = data.py().import("datetime")?.getattr("timedelta")?;
+    if value.is_instance(&timedelta)? {
+        return Ok(Some(value.call_method0("total_seconds")?.extract()?));
+    }
+    Ok(value.extract().ok())
+}
+
+/// Rebuilds a reading from a dictionary returned by `as_dict`.
+///
+/// # Returns
+///
+/// The battery index, the reading, and the timestamp if the dictionary has one.
+pub fn from_dict(data: &Bound<'_, PyDict>) -> PyResult<(usize, Reading, Option<f64>)> {
+    let optional = |key: &str| -> PyResult<Option<Bound<'_, PyAny>>> {
+        Ok(data.get_item(key)?.filter(|value| !value.is_none()))
+    };
+    let required = |key: &str, base_units: &str| match optional(key)? {
+        Some(value) => measurement_from(&value, key, base_units),
+        None => Err(PyValueError::new_err(format!("Missing field: {:?}", key))),
+    };
+    let string = |key: &str| optional(key)?.map(|value| value.extract()).transpose();
+
+    let technology = optional("technology")?
+        .map(|value| value.extract::<Named<BatteryTechnology>>())
+        .transpose()?
+        .map_or(BatteryTechnology::Unknown, |t| t.resolve(parse_technology));
+    let state = optional("state")?
+        .map(|value| value.extract::<Named<BatteryState>>())
+        .transpose()?
+        .map_or(BatteryState::Unknown, |s| s.resolve(parse_state));
+    let temperature = optional("temperature")?
+        .map(|value| measurement_from(&value, "temperature", "°C"))
+        .transpose()?;
+
+    let reading = Reading {
+        vendor: string("vendor")?,
+        model: string("model")?,
+        serial_number: string("serial_number")?,
+        technology,
+        state,
+        state_of_charge: required("percent", "%")?,
+        state_of_health: required("capacity", "%")?,
+        temperature,
+        cycle_count: optional("cycle_count")?.map(|c| c.extract()).transpose()?,
+        energy: required("energy", "Wh")?,
+        energy_full: required("energy_full", "Wh")?,
+        energy_full_design: required("energy_full_design", "Wh")?,
+        energy_rate: required("energy_rate", "W")?,
+        voltage: required("voltage", "V")?,
+        time_to_empty: seconds_from(data, "time_to_empty")?,
+        time_to_full: seconds_from(data, "time_to_full")?,
+    };
+    let index = optional("battery_index")?.map_or(Ok(0), |i| i.extract())?;
+    let timestamp = optional("timestamp")?.map(|t| t.extract()).transpose()?;
+    Ok((index, reading, timestamp))
+}
@@ -0,0 +1,138 @@
+//! Applying display settings to battery readings.
+//!
+//! The settings of a `Battery` (time format, temperature unit, precision and locale) live in
+//! `DisplaySettings`, which has no Python dependencies so the `batteryinfo` command shows the
+//! same values as the Python module.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::duration::{FormattedTime, format_time};
+use crate::enums::{TempUnit, TimeFormat};
+use crate::error::{Error, Result};
+use crate::locale::{self, Locale};
+use crate::measurement::Measurement;
+use crate::snapshot::BatterySnapshot;
+use crate::source::Reading;
+use crate::units::{convert, widen};
+
+/// The fields shown as measurements, which can each have their own number of decimals.
+pub const MEASUREMENT_FIELDS: [&str; 8] = [
+    "percent",
+    "capacity",
+    "temperature",
+    "energy",
+    "energy_full",
+    "energy_full_design",
+    "energy_rate",
+    "voltage",
+];
+
+/// The number of decimals displayed for a field without a configured precision.
+pub const DEFAULT_DECIMALS: usize = 1;
+
+/// Checks that every field in a precision configuration is shown as a measurement.
+pub fn check_precision(precision: HashMap<String, usize>) -> Result<HashMap<String, usize>> {
+    if let Some(field) = precision
+        .keys()
+        .find(|field| !MEASUREMENT_FIELDS.contains(&field.as_str()))
+    {
+        return Err(Error::UnknownField(field.clone()));
+    }
+    Ok(precision)
+}
+
+/// How battery information is displayed.
+#[derive(Debug, Clone)]
+pub struct DisplaySettings {
+    /// The format for displaying time.
+    pub time_format: TimeFormat,
+    /// The unit for displaying temperature.
+    pub temp_unit: TempUnit,
+    /// The number of decimals to display for each field that does not use the default.
+    pub precision: HashMap<String, usize>,
+    /// The locale for displaying times and numbers, or `None` to use the global locale.
+    pub locale: Option<String>,
+}
+
+impl DisplaySettings {
+    /// Creates display settings with the default precision and the global locale.
+    pub fn new(time_format: TimeFormat, temp_unit: TempUnit) -> Self {
+        DisplaySettings {
+            time_format,
+            temp_unit,
+            precision: HashMap::new(),
+            locale: None,
+        }
+    }
+
+    /// Returns the locale for displaying times and numbers.
+    pub fn locale(&self) -> Arc<Locale> {
+        locale::resolve(self.locale.as_deref())
+    }
+
+    /// Creates a measurement for display, with the number of decimals configured for the field.
+    pub fn measure(&self, field: &str, value: f32, units: &str) -> Measurement {
+        let decimals = self
+            .precision
+            .get(field)
+            .copied()
+            .unwrap_or(DEFAULT_DECIMALS);
+        Measurement {
+            value,
+            units: units.to_string(),
+            decimals,
+            decimal_separator: self.locale().decimal_separator,
+        }
+    }
+
+    /// Returns the temperature of a reading as a measurement in the given unit.
+    pub fn temperature_in(&self, reading: &Reading, unit: TempUnit) -> Option<Measurement> {
+        reading.temperature.map(|celsius| {
+            let value = convert(celsius as f64, TempUnit::DegC.symbol(), unit.symbol())
+                .expect("temperature units are convertible");
+            self.measure("temperature", value as f32, unit.symbol())
+        })
+    }
+
+    /// Formats a duration in seconds using the configured time format.
+    pub fn format_time(&self, seconds: Option<f32>) -> Option<FormattedTime> {
+        seconds.map(|seconds| format_time(seconds, self.time_format, &self.locale()))
+    }
+
+    /// Returns a snapshot of a reading.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the battery.
+    /// * `reading` - The battery information.
+    /// * `timestamp` - The time the reading was taken, in seconds since the Unix epoch.
+    pub fn snapshot(&self, index: usize, reading: &Reading, timestamp: f64) -> BatterySnapshot {
+        BatterySnapshot {
+            index,
+            vendor: reading.vendor.clone(),
+            model: reading.model.clone(),
+            serial_number: reading.serial_number.clone(),
+            technology: reading.technology,
+            percent: self.measure("percent", reading.state_of_charge, "%"),
+            state: reading.state,
+            capacity: self.measure("capacity", reading.state_of_health, "%"),
+            temperature: self.temperature_in(reading, self.temp_unit),
+            cycle_count: reading.cycle_count,
+            energy: self.measure("energy", reading.energy, "Wh"),
+            energy_full: self.measure("energy_full", reading.energy_full, "Wh"),
+            energy_full_design: self.measure(
+                "energy_full_design",
+                reading.energy_full_design,
+                "Wh",
+            ),
+            energy_rate: self.measure("energy_rate", reading.energy_rate, "W"),
+            voltage: self.measure("voltage", reading.voltage, "V"),
+            time_to_empty: self.format_time(reading.time_to_empty),
+            time_to_full: self.format_time(reading.time_to_full),
+            time_to_empty_seconds: reading.time_to_empty.map(widen),
+            time_to_full_seconds: reading.time_to_full.map(widen),
+            timestamp,
+        }
+    }
+}
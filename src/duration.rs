@@ -0,0 +1,247 @@
+//! Formatting durations, such as the time to empty a battery, for display.
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use std::fmt;
+
+use crate::enums::TimeFormat;
+use crate::locale::{DAY, HOUR, Locale, MINUTE, SECOND};
+use crate::units::widen;
+
+/// A duration formatted with a `TimeFormat`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FormattedTime {
+    /// A duration formatted as text.
+    Text(String),
+    /// A duration in seconds, given to Python as a `datetime.timedelta`.
+    Timedelta(f64),
+}
+
+#[cfg(feature = "python")]
+impl<'py> IntoPyObject<'py> for FormattedTime {
+    type Target = PyAny;
+    type Output = Bound<'py, PyAny>;
+    type Error = PyErr;
+
+    fn into_pyobject(self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        match self {
+            FormattedTime::Text(text) => Ok(text.into_pyobject(py)?.into_any()),
+            FormattedTime::Timedelta(seconds) => {
+                let timedelta = py.import("datetime")?.getattr("timedelta")?;
+                timedelta.call1((0, seconds))
+            }
+        }
+    }
+}
+
+/// Displays a `Timedelta` the way Python's `str()` displays a `datetime.timedelta`, e.g.
+/// `1:25:52` or `1 day, 2:00:00`.
+impl fmt::Display for FormattedTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormattedTime::Text(text) => write!(f, "{}", text),
+            FormattedTime::Timedelta(seconds) => {
+                let micros = (seconds.max(0.0) * 1e6).round() as u64;
+                let (whole, micros) = (micros / 1_000_000, micros % 1_000_000);
+                let days = whole / 86400;
+                if days > 0 {
+                    write!(f, "{} day{}, ", days, if days == 1 { "" } else { "s" })?;
+                }
+                write!(
+                    f,
+                    "{}:{:02}:{:02}",
+                    whole % 86400 / 3600,
+                    whole % 3600 / 60,
+                    whole % 60
+                )?;
+                if micros > 0 {
+                    write!(f, ".{:06}", micros)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Formats a duration in seconds.
+///
+/// # Arguments
+///
+/// * `seconds` - The duration in seconds.
+/// * `format` - The format to use.
+/// * `locale` - The locale for the words and numbers of the `Seconds`, `Minutes`, `Human` and
+///   `Approximate` formats. The other formats are the same in every locale.
+pub fn format_time(seconds: f32, format: TimeFormat, locale: &Locale) -> FormattedTime {
+    let whole = seconds.max(0.0) as u64;
+    FormattedTime::Text(match format {
+        TimeFormat::Seconds => {
+            locale.unit(SECOND, &locale.number(&format!("{:.1}", seconds)), true)
+        }
+        TimeFormat::Minutes => locale.unit(
+            MINUTE,
+            &locale.number(&format!("{:.1}", seconds / 60.0)),
+            true,
+        ),
+        TimeFormat::Human => human(whole, locale),
+        TimeFormat::Timedelta => return FormattedTime::Timedelta(widen(seconds)),
+        TimeFormat::HoursMinutes => format!("{}:{:02}", whole / 3600, whole % 3600 / 60),
+        TimeFormat::Iso8601 => iso8601(whole),
+        TimeFormat::Compact => compact(whole),
+        TimeFormat::Approximate => approximate(seconds, locale),
+    })
+}
+
+/// Formats whole seconds with the abbreviated units of a locale, e.g. `1h,25m,52s`, leaving
+/// out the parts that are zero.
+fn human(seconds: u64, locale: &Locale) -> String {
+    let amounts = [
+        seconds / 86400,
+        seconds % 86400 / 3600,
+        seconds % 3600 / 60,
+        seconds % 60,
+    ];
+    let parts: Vec<String> = amounts
+        .iter()
+        .zip(&locale.short_units)
+        .filter(|(amount, _)| **amount > 0)
+        .map(|(amount, template)| template.replace("{}", &amount.to_string()))
+        .collect();
+    if parts.is_empty() {
+        return locale.short_units[SECOND].replace("{}", "0");
+    }
+    parts.join(&locale.separator)
+}
+
+/// Formats whole seconds as an ISO 8601 duration, leaving out the parts that are zero.
+fn iso8601(seconds: u64) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds % 3600 / 60, seconds % 60);
+    let mut text = String::from("PT");
+    if hours > 0 {
+        text += &format!("{}H", hours);
+    }
+    if minutes > 0 {
+        text += &format!("{}M", minutes);
+    }
+    if seconds > 0 || text.len() == 2 {
+        text += &format!("{}S", seconds);
+    }
+    text
+}
+
+/// Formats whole seconds as hours and minutes, or as seconds under a minute.
+fn compact(seconds: u64) -> String {
+    match (seconds / 3600, seconds % 3600 / 60) {
+        (0, 0) => format!("{}s", seconds),
+        (0, minutes) => format!("{}m", minutes),
+        (hours, 0) => format!("{}h", hours),
+        (hours, minutes) => format!("{}h{}m", hours, minutes),
+    }
+}
+
+/// Formats a duration as a rounded approximation.
+///
+/// The longer the duration, the coarser the rounding, since estimates far in the future are
+/// less accurate: to the minute under 10 minutes, to 5 minutes under an hour, to half an hour
+/// under 10 hours, to the hour under 2 days, and to the day beyond.
+fn approximate(seconds: f32, locale: &Locale) -> String {
+    let minutes = seconds / 60.0;
+    let hours = minutes / 60.0;
+    let about = |unit: usize, amount: u64, half: bool| {
+        let text = if half {
+            format!("{}½", amount)
+        } else {
+            amount.to_string()
+        };
+        let unit = locale.unit(unit, &text, half || amount != 1);
+        locale.about.replace("{}", &unit)
+    };
+    if minutes < 1.0 {
+        locale.less_than_a_minute.clone()
+    } else if minutes < 9.5 {
+        about(MINUTE, minutes.round() as u64, false)
+    } else if minutes < 57.5 {
+        about(MINUTE, (minutes / 5.0).round() as u64 * 5, false)
+    } else if hours < 9.75 {
+        let halves = (hours * 2.0).round() as u64;
+        about(HOUR, halves / 2, halves % 2 == 1)
+    } else if hours < 47.5 {
+        about(HOUR, hours.round() as u64, false)
+    } else {
+        about(DAY, (hours / 24.0).round() as u64, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::locale;
+
+    /// Formats a duration in the English locale.
+    fn format(seconds: f32, format: TimeFormat) -> String {
+        format_time(seconds, format, &locale::find("en").unwrap()).to_string()
+    }
+
+    #[test]
+    fn approximates_at_the_band_boundaries() {
+        let cases = [
+            (0.0, "less than a minute"),
+            (59.0, "less than a minute"),
+            (60.0, "about 1 minute"),
+            (119.0, "about 2 minutes"),
+            (569.0, "about 9 minutes"),
+            (599.0, "about 10 minutes"),
+            (600.0, "about 10 minutes"),
+            (1_000.0, "about 15 minutes"),
+            (3_420.0, "about 55 minutes"),
+            (3_540.0, "about 1 hour"),
+            (3_600.0, "about 1 hour"),
+            (5_400.0, "about 1½ hours"),
+            (34_200.0, "about 9½ hours"),
+            (35_940.0, "about 10 hours"),
+            (36_000.0, "about 10 hours"),
+            (169_200.0, "about 47 hours"),
+            (172_800.0, "about 2 days"),
+            (216_000.0, "about 3 days"),
+        ];
+        for (seconds, expected) in cases {
+            let text = format(seconds, TimeFormat::Approximate);
+            assert_eq!(text, expected, "{}", seconds);
+        }
+    }
+
+    #[test]
+    fn formats_clock_iso8601_and_compact() {
+        let cases = [
+            (0.0, "0:00", "PT0S", "0s"),
+            (59.0, "0:00", "PT59S", "59s"),
+            (60.0, "0:01", "PT1M", "1m"),
+            (3_600.0, "1:00", "PT1H", "1h"),
+            (5_452.9, "1:30", "PT1H30M52S", "1h30m"),
+            (90_061.0, "25:01", "PT25H1M1S", "25h1m"),
+        ];
+        for (seconds, clock, iso8601, compact) in cases {
+            assert_eq!(format(seconds, TimeFormat::HoursMinutes), clock);
+            assert_eq!(format(seconds, TimeFormat::Iso8601), iso8601);
+            assert_eq!(format(seconds, TimeFormat::Compact), compact);
+        }
+    }
+
+    #[test]
+    fn formats_human_seconds_and_minutes() {
+        assert_eq!(format(0.0, TimeFormat::Human), "0s");
+        assert_eq!(format(5_452.0, TimeFormat::Human), "1h,30m,52s");
+        assert_eq!(format(90_000.0, TimeFormat::Human), "1d,1h");
+        assert_eq!(format(90.0, TimeFormat::Seconds), "90.0 seconds");
+        assert_eq!(format(90.0, TimeFormat::Minutes), "1.5 minutes");
+    }
+
+    #[test]
+    fn displays_timedeltas_like_python() {
+        assert_eq!(format(5_452.0, TimeFormat::Timedelta), "1:30:52");
+        assert_eq!(
+            format(90_061.5, TimeFormat::Timedelta),
+            "1 day, 1:01:01.500000"
+        );
+        assert_eq!(format(-1.0, TimeFormat::Timedelta), "0:00:00");
+    }
+}
@@ -1,7 +1,35 @@
+#[cfg(feature = "python")]
+use pyo3::IntoPyObjectExt;
+#[cfg(feature = "python")]
 use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3::pyclass::CompareOp;
+#[cfg(feature = "python")]
+use pyo3::types::PyString;
+use serde::{Serialize, Serializer};
+
+/// An enum value given either directly or by name, e.g. `BatteryState.Charging` or
+/// `"Charging"`.
+#[cfg(feature = "python")]
+#[derive(Debug, FromPyObject)]
+pub enum Named<T> {
+    Value(T),
+    Name(String),
+}
+
+#[cfg(feature = "python")]
+impl<T> Named<T> {
+    /// Returns the value, parsing it with `parse` if it was given by name.
+    pub fn resolve(self, parse: fn(&str) -> T) -> T {
+        match self {
+            Named::Value(value) => value,
+            Named::Name(name) => parse(&name),
+        }
+    }
+}
 
 /// Represents the format for displaying time.
-#[pyclass(eq, eq_int)]
+#[cfg_attr(feature = "python", pyclass(eq, eq_int))]
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum TimeFormat {
     /// Display time in seconds.
@@ -10,8 +38,40 @@ pub enum TimeFormat {
     Minutes,
     /// Display time in a human-readable format.
     Human,
+    /// Return time as a `datetime.timedelta`.
+    Timedelta,
+    /// Display time as hours and minutes on a clock, e.g. `1:25`.
+    HoursMinutes,
+    /// Display time as an ISO 8601 duration, e.g. `PT1H25M52S`.
+    Iso8601,
+    /// Display time in hours and minutes without spaces, e.g. `1h25m`.
+    Compact,
+    /// Display time as a rounded approximation, e.g. `about 1½ hours`.
+    Approximate,
+}
+
+impl TimeFormat {
+    /// Every time format.
+    pub const ALL: [TimeFormat; 8] = [
+        TimeFormat::Seconds,
+        TimeFormat::Minutes,
+        TimeFormat::Human,
+        TimeFormat::Timedelta,
+        TimeFormat::HoursMinutes,
+        TimeFormat::Iso8601,
+        TimeFormat::Compact,
+        TimeFormat::Approximate,
+    ];
+
+    /// Finds a time format by name, ignoring case, e.g. `human` or `HoursMinutes`.
+    pub fn find(name: &str) -> Option<TimeFormat> {
+        TimeFormat::ALL
+            .into_iter()
+            .find(|format| format!("{:?}", format).eq_ignore_ascii_case(name))
+    }
 }
 
+#[cfg(feature = "python")]
 #[pymethods]
 impl TimeFormat {
     /// Returns a string representation of the time format.
@@ -21,19 +81,356 @@ impl TimeFormat {
 }
 
 /// Represents the unit for displaying temperature.
-#[pyclass(eq, eq_int)]
+#[cfg_attr(feature = "python", pyclass(eq, eq_int))]
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum TempUnit {
     /// Display temperature in degrees Celsius.
     DegC,
     /// Display temperature in degrees Fahrenheit.
     DegF,
+    /// Display temperature in kelvins.
+    Kelvin,
 }
 
+impl TempUnit {
+    /// Every temperature unit.
+    pub const ALL: [TempUnit; 3] = [TempUnit::DegC, TempUnit::DegF, TempUnit::Kelvin];
+
+    /// Finds a temperature unit by name, ignoring case, e.g. `degc` or `Kelvin`.
+    pub fn find(name: &str) -> Option<TempUnit> {
+        TempUnit::ALL
+            .into_iter()
+            .find(|unit| format!("{:?}", unit).eq_ignore_ascii_case(name))
+    }
+
+    /// Returns the symbol of the unit, e.g. `°C`.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            TempUnit::DegC => "°C",
+            TempUnit::DegF => "°F",
+            TempUnit::Kelvin => "K",
+        }
+    }
+}
+
+#[cfg(feature = "python")]
 #[pymethods]
 impl TempUnit {
     /// Returns a string representation of the temperature unit.
     fn __repr__(&self) -> PyResult<String> {
         Ok(format!("{:?}", self))
     }
-}
\ No newline at end of file
+}
+
+/// Represents a status bar that battery information can be rendered for.
+#[cfg_attr(feature = "python", pyclass(eq, eq_int))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StatusBar {
+    /// A Waybar custom module, as JSON with `text`, `tooltip`, `class` and `percentage`.
+    Waybar,
+    /// An i3blocks blocklet, as the full text, the short text and the color on three lines.
+    I3blocks,
+    /// A block of the i3bar protocol, as JSON.
+    I3bar,
+    /// A Polybar format string, with `%{F...}` color tags.
+    Polybar,
+    /// A tmux status segment, with `#[fg=...]` style tags.
+    Tmux,
+}
+
+impl StatusBar {
+    /// Every status bar.
+    pub const ALL: [StatusBar; 5] = [
+        StatusBar::Waybar,
+        StatusBar::I3blocks,
+        StatusBar::I3bar,
+        StatusBar::Polybar,
+        StatusBar::Tmux,
+    ];
+
+    /// Finds a status bar by name, ignoring case, e.g. `waybar` or `I3bar`.
+    pub fn find(name: &str) -> Option<StatusBar> {
+        StatusBar::ALL
+            .into_iter()
+            .find(|bar| format!("{:?}", bar).eq_ignore_ascii_case(name))
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl StatusBar {
+    /// Returns a string representation of the status bar.
+    fn __repr__(&self) -> PyResult<String> {
+        Ok(format!("{:?}", self))
+    }
+}
+
+/// Represents the state of a battery.
+///
+/// A state compares equal to its name, e.g. `BatteryState.Charging == "Charging"`, so code that
+/// compares against the names keeps working.
+#[cfg_attr(feature = "python", pyclass(frozen))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatteryState {
+    /// The battery is charging.
+    Charging,
+    /// The battery is discharging.
+    Discharging,
+    /// The battery is full.
+    Full,
+    /// The battery is empty.
+    Empty,
+    /// The battery is on external power but not charging, e.g. because of a charge limit.
+    NotCharging,
+    /// The state is not known.
+    Unknown,
+}
+
+impl BatteryState {
+    /// Every state.
+    pub const ALL: [BatteryState; 6] = [
+        BatteryState::Charging,
+        BatteryState::Discharging,
+        BatteryState::Full,
+        BatteryState::Empty,
+        BatteryState::NotCharging,
+        BatteryState::Unknown,
+    ];
+
+    /// Returns the name of the state.
+    pub fn name(self) -> &'static str {
+        match self {
+            BatteryState::Charging => "Charging",
+            BatteryState::Discharging => "Discharging",
+            BatteryState::Full => "Full",
+            BatteryState::Empty => "Empty",
+            BatteryState::NotCharging => "NotCharging",
+            BatteryState::Unknown => "Unknown",
+        }
+    }
+
+    /// Returns whether the system is running on external power. This is the case when the
+    /// battery is charging, full, or not charging.
+    pub fn on_external_power(self) -> bool {
+        matches!(
+            self,
+            BatteryState::Charging | BatteryState::Full | BatteryState::NotCharging
+        )
+    }
+}
+
+/// Serializes as the name of the state, e.g. `NotCharging`.
+impl Serialize for BatteryState {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl From<battery::State> for BatteryState {
+    fn from(state: battery::State) -> Self {
+        match state {
+            battery::State::Charging => BatteryState::Charging,
+            battery::State::Discharging => BatteryState::Discharging,
+            battery::State::Full => BatteryState::Full,
+            battery::State::Empty => BatteryState::Empty,
+            _ => BatteryState::Unknown,
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl BatteryState {
+    /// Returns whether the battery is charging.
+    #[getter]
+    fn is_charging(&self) -> PyResult<bool> {
+        Ok(*self == BatteryState::Charging)
+    }
+
+    /// Returns whether the battery is discharging.
+    #[getter]
+    fn is_discharging(&self) -> PyResult<bool> {
+        Ok(*self == BatteryState::Discharging)
+    }
+
+    /// Returns whether the system is running on external power. This is the case when the
+    /// battery is charging, full, or not charging.
+    #[getter]
+    fn is_on_ac(&self) -> PyResult<bool> {
+        Ok(self.on_external_power())
+    }
+
+    /// Compares the state with another state or with a state name.
+    fn __richcmp__(&self, other: &Bound<'_, PyAny>, op: CompareOp) -> PyResult<PyObject> {
+        let py = other.py();
+        let equal = if let Ok(other) = other.downcast::<BatteryState>() {
+            *self == *other.get()
+        } else if let Ok(name) = other.extract::<String>() {
+            name == self.name()
+        } else {
+            return Ok(py.NotImplemented());
+        };
+        match op {
+            CompareOp::Eq => equal.into_py_any(py),
+            CompareOp::Ne => (!equal).into_py_any(py),
+            _ => Ok(py.NotImplemented()),
+        }
+    }
+
+    /// Returns the hash of the name, so that a state and its name hash the same.
+    fn __hash__(&self, py: Python<'_>) -> PyResult<isize> {
+        PyString::new(py, self.name()).hash()
+    }
+
+    /// Returns a string representation of the battery state.
+    fn __repr__(&self) -> PyResult<String> {
+        Ok(self.name().to_string())
+    }
+}
+
+/// Represents the chemistry of a battery, with typical figures for it.
+///
+/// A technology compares equal to its string form, e.g. `"lithium-ion"`, which is what the
+/// `technology` getter returned before it returned this enum.
+#[cfg_attr(feature = "python", pyclass(frozen))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatteryTechnology {
+    /// Lithium-ion.
+    LiIon,
+    /// Lithium polymer.
+    LiPo,
+    /// Lithium iron phosphate.
+    LiFePO4,
+    /// Nickel-metal hydride.
+    NiMH,
+    /// Nickel-cadmium.
+    NiCd,
+    /// Lead-acid.
+    LeadAcid,
+    /// The technology is not known.
+    Unknown,
+}
+
+impl BatteryTechnology {
+    /// Returns the name of the technology.
+    pub fn name(self) -> &'static str {
+        match self {
+            BatteryTechnology::LiIon => "LiIon",
+            BatteryTechnology::LiPo => "LiPo",
+            BatteryTechnology::LiFePO4 => "LiFePO4",
+            BatteryTechnology::NiMH => "NiMH",
+            BatteryTechnology::NiCd => "NiCd",
+            BatteryTechnology::LeadAcid => "LeadAcid",
+            BatteryTechnology::Unknown => "Unknown",
+        }
+    }
+
+    /// Returns the string form of the technology, e.g. `lithium-ion`.
+    pub fn description(self) -> &'static str {
+        match self {
+            BatteryTechnology::LiIon => "lithium-ion",
+            BatteryTechnology::LiPo => "lithium-polymer",
+            BatteryTechnology::LiFePO4 => "lithium-iron-phosphate",
+            BatteryTechnology::NiMH => "nickel-metal-hydride",
+            BatteryTechnology::NiCd => "nickel-cadmium",
+            BatteryTechnology::LeadAcid => "lead-acid",
+            BatteryTechnology::Unknown => "unknown",
+        }
+    }
+}
+
+/// Serializes as the name of the technology, e.g. `LiIon`.
+impl Serialize for BatteryTechnology {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl From<battery::Technology> for BatteryTechnology {
+    fn from(technology: battery::Technology) -> Self {
+        match technology {
+            battery::Technology::LithiumIon => BatteryTechnology::LiIon,
+            battery::Technology::LithiumPolymer => BatteryTechnology::LiPo,
+            battery::Technology::LithiumIronPhosphate => BatteryTechnology::LiFePO4,
+            battery::Technology::NickelMetalHydride => BatteryTechnology::NiMH,
+            battery::Technology::NickelCadmium => BatteryTechnology::NiCd,
+            battery::Technology::LeadAcid => BatteryTechnology::LeadAcid,
+            _ => BatteryTechnology::Unknown,
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl BatteryTechnology {
+    /// Returns the nominal voltage of a single cell in volts, or `None` if the technology is
+    /// not known.
+    #[getter]
+    fn nominal_voltage(&self) -> PyResult<Option<f64>> {
+        Ok(match self {
+            BatteryTechnology::LiIon => Some(3.6),
+            BatteryTechnology::LiPo => Some(3.7),
+            BatteryTechnology::LiFePO4 => Some(3.2),
+            BatteryTechnology::NiMH | BatteryTechnology::NiCd => Some(1.2),
+            BatteryTechnology::LeadAcid => Some(2.0),
+            BatteryTechnology::Unknown => None,
+        })
+    }
+
+    /// Returns the typical number of full charge cycles before the capacity drops to about
+    /// 80% of the design capacity, or `None` if the technology is not known.
+    #[getter]
+    fn cycle_life(&self) -> PyResult<Option<u32>> {
+        Ok(match self {
+            BatteryTechnology::LiIon => Some(500),
+            BatteryTechnology::LiPo => Some(400),
+            BatteryTechnology::LiFePO4 => Some(2000),
+            BatteryTechnology::NiMH => Some(500),
+            BatteryTechnology::NiCd => Some(1000),
+            BatteryTechnology::LeadAcid => Some(300),
+            BatteryTechnology::Unknown => None,
+        })
+    }
+
+    /// Returns whether the technology is lithium-based.
+    #[getter]
+    fn is_lithium(&self) -> PyResult<bool> {
+        Ok(matches!(
+            self,
+            BatteryTechnology::LiIon | BatteryTechnology::LiPo | BatteryTechnology::LiFePO4
+        ))
+    }
+
+    /// Compares the technology with another technology or with a string form.
+    fn __richcmp__(&self, other: &Bound<'_, PyAny>, op: CompareOp) -> PyResult<PyObject> {
+        let py = other.py();
+        let equal = if let Ok(other) = other.downcast::<BatteryTechnology>() {
+            *self == *other.get()
+        } else if let Ok(description) = other.extract::<String>() {
+            description == self.description()
+        } else {
+            return Ok(py.NotImplemented());
+        };
+        match op {
+            CompareOp::Eq => equal.into_py_any(py),
+            CompareOp::Ne => (!equal).into_py_any(py),
+            _ => Ok(py.NotImplemented()),
+        }
+    }
+
+    /// Returns the hash of the string form, so that a technology and its string form hash the
+    /// same.
+    fn __hash__(&self, py: Python<'_>) -> PyResult<isize> {
+        PyString::new(py, self.description()).hash()
+    }
+
+    /// Returns the string form of the technology, e.g. `lithium-ion`.
+    fn __str__(&self) -> PyResult<String> {
+        Ok(self.description().to_string())
+    }
+
+    /// Returns a string representation of the battery technology.
+    fn __repr__(&self) -> PyResult<String> {
+        Ok(self.name().to_string())
+    }
+}
@@ -0,0 +1,91 @@
+#[cfg(feature = "python")]
+use pyo3::exceptions::{PyIndexError, PyOSError, PyRuntimeError, PyValueError};
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use crate::display::MEASUREMENT_FIELDS;
+
+/// Errors that can occur while reading battery information.
+#[derive(Debug)]
+pub enum Error {
+    /// The system has no batteries.
+    NoBatteries,
+    /// The requested battery index does not exist.
+    IndexOutOfRange,
+    /// The platform battery API reported an error.
+    Platform(String),
+    /// A file could not be read or written.
+    Io(PathBuf, io::Error),
+    /// A server could not listen on an address.
+    Bind(String, io::Error),
+    /// Data read from a file is not valid.
+    InvalidData(String),
+    /// A unit of measurement is not known.
+    UnknownUnit(String),
+    /// A measurement cannot be converted or combined between two units.
+    IncompatibleUnits(String, String),
+    /// A locale is not registered.
+    UnknownLocale(String),
+    /// A locale being registered is not valid.
+    InvalidLocale(String),
+    /// A precision was given for a field that is not shown as a measurement.
+    UnknownField(String),
+    /// A status bar style is not valid.
+    InvalidStyle(String),
+    /// A format template is not valid.
+    InvalidTemplate(String),
+}
+
+/// A specialized `Result` type for reading battery information.
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NoBatteries => write!(f, "No batteries found"),
+            Error::IndexOutOfRange => write!(f, "Battery index out of range"),
+            Error::Platform(message) => write!(f, "{}", message),
+            Error::Io(path, e) => write!(f, "Failed to access {}: {}", path.display(), e),
+            Error::Bind(address, e) => write!(f, "Failed to listen on {}: {}", address, e),
+            Error::InvalidData(message) => write!(f, "Invalid data: {}", message),
+            Error::UnknownUnit(unit) => write!(f, "Unknown unit: {:?}", unit),
+            Error::IncompatibleUnits(from, to) => {
+                write!(f, "Incompatible units: {:?} and {:?}", from, to)
+            }
+            Error::UnknownLocale(code) => write!(f, "Unknown locale: {:?}", code),
+            Error::InvalidLocale(message) => write!(f, "Invalid locale: {}", message),
+            Error::UnknownField(field) => write!(
+                f,
+                "Unknown precision field: {:?} (expected one of {})",
+                field,
+                MEASUREMENT_FIELDS.join(", ")
+            ),
+            Error::InvalidStyle(message) => write!(f, "Invalid status style: {}", message),
+            Error::InvalidTemplate(message) => write!(f, "Invalid template: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(feature = "python")]
+impl From<Error> for PyErr {
+    fn from(error: Error) -> Self {
+        match error {
+            Error::IndexOutOfRange => PyIndexError::new_err(error.to_string()),
+            Error::Io(..) | Error::Bind(..) => PyOSError::new_err(error.to_string()),
+            Error::InvalidData(_)
+            | Error::UnknownUnit(_)
+            | Error::IncompatibleUnits(..)
+            | Error::UnknownLocale(_)
+            | Error::InvalidLocale(_)
+            | Error::UnknownField(_)
+            | Error::InvalidStyle(_)
+            | Error::InvalidTemplate(_) => PyValueError::new_err(error.to_string()),
+            _ => PyRuntimeError::new_err(error.to_string()),
+        }
+    }
+}
@@ -0,0 +1,29 @@
+//! JSON serialization of battery information.
+//!
+//! Payloads follow a versioned schema, published in `schema/battery.schema.json`. The version
+//! changes when a field is removed or changes meaning. Fields may be added within a version.
+
+use serde::Serialize;
+
+use crate::error::{Error, Result};
+
+/// The version of the JSON schema, written to every payload as `schema_version`.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The JSON Schema document that payloads validate against.
+pub const SCHEMA: &str = include_str!("../schema/battery.schema.json");
+
+/// Serializes a value to JSON.
+///
+/// # Arguments
+///
+/// * `value` - The value to serialize.
+/// * `pretty` - Whether to indent the JSON over several lines.
+pub fn to_string<T: Serialize>(value: &T, pretty: bool) -> Result<String> {
+    let json = if pretty {
+        serde_json::to_string_pretty(value)
+    } else {
+        serde_json::to_string(value)
+    };
+    json.map_err(|e| Error::InvalidData(e.to_string()))
+}
@@ -1,27 +1,34 @@
 //! A cross-platform Python module, built with Rust, for obtaining comprehensive system battery information
 //! including status, capacity, and temperature.
+//!
+//! The Python module is built with the `python` feature, which is on by default. The modules
+//! that do not depend on Python are public, and are shared with the standalone `batteryinfo`
+//! command built with the `cli` feature.
 
+#[cfg(feature = "python")]
+mod asyncio;
+#[cfg(feature = "python")]
 mod battery;
-mod measurement;
-mod enums;
+#[cfg(feature = "python")]
+mod dict;
+#[cfg(feature = "python")]
+mod monitor;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "python")]
+mod testing;
 
-use pyo3::prelude::*;
-use battery::Battery;
-use measurement::Measurement;
-use enums::{TimeFormat, TempUnit};
-
-/// The `batteryinfo` module provides classes and functions to interact with system batteries.
-///
-/// This module includes the following classes:
-/// - `Battery`: Represents a system battery with properties like charge, voltage, and temperature.
-/// - `Measurement`: Represents a measurement with a value, units, and precision.
-/// - `TimeFormat`: Enum representing the format for displaying time.
-/// - `TempUnit`: Enum representing the unit for displaying temperature.
-#[pymodule]
-fn batteryinfo(m: &Bound<'_, PyModule>) -> PyResult<()> {
-    m.add_class::<Battery>()?;
-    m.add_class::<Measurement>()?;
-    m.add_class::<TimeFormat>()?;
-    m.add_class::<TempUnit>()?;
-    Ok(())
-}
\ No newline at end of file
+pub mod display;
+pub mod duration;
+pub mod enums;
+pub mod error;
+pub mod json;
+pub mod locale;
+pub mod measurement;
+pub mod prometheus;
+pub mod record;
+pub mod snapshot;
+pub mod source;
+pub mod status;
+pub mod template;
+pub mod units;
@@ -0,0 +1,396 @@
+//! Locales for displaying durations and numbers in other languages.
+//!
+//! The built-in locales are `en`, `de`, `fr`, `es` and `ja`. More can be registered from
+//! Python with `register_locale`. The global locale is used by every `Battery` that does not
+//! have a locale of its own.
+
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, RwLock};
+
+use crate::error::{Error, Result};
+
+/// The units of time, largest first, in the order used by `Locale`.
+pub const TIME_UNITS: [&str; 4] = ["day", "hour", "minute", "second"];
+
+/// The positions of the units of time in `TIME_UNITS`.
+pub const DAY: usize = 0;
+pub const HOUR: usize = 1;
+pub const MINUTE: usize = 2;
+pub const SECOND: usize = 3;
+
+/// The words and separators used to display durations and numbers in a language.
+///
+/// Words are templates in which `{}` is replaced by the number, e.g. `{} hours` or `{}時間`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Locale {
+    /// The decimal separator, e.g. `,`.
+    pub decimal_separator: char,
+    /// The abbreviated units of time used by `TimeFormat.Human`, largest first, e.g. `{}h`.
+    pub short_units: [String; 4],
+    /// The separator between the parts of a `TimeFormat.Human` duration, e.g. `,`.
+    pub separator: String,
+    /// The singular and plural units of time, largest first, e.g. `{} hour` and `{} hours`.
+    pub units: [(String, String); 4],
+    /// The template for approximate durations, e.g. `about {}`.
+    pub about: String,
+    /// The text for approximate durations under a minute.
+    pub less_than_a_minute: String,
+}
+
+impl Locale {
+    /// Creates a locale from string literals.
+    fn new(
+        decimal_separator: char,
+        short_units: [&str; 4],
+        separator: &str,
+        units: [(&str, &str); 4],
+        about: &str,
+        less_than_a_minute: &str,
+    ) -> Self {
+        Locale {
+            decimal_separator,
+            short_units: short_units.map(str::to_string),
+            separator: separator.to_string(),
+            units: units.map(|(singular, plural)| (singular.to_string(), plural.to_string())),
+            about: about.to_string(),
+            less_than_a_minute: less_than_a_minute.to_string(),
+        }
+    }
+
+    /// Replaces some of the abbreviated units of time, given by unit name, e.g. `hour`.
+    pub fn set_short_units(&mut self, short_units: HashMap<String, String>) -> Result<()> {
+        for (unit, template) in short_units {
+            self.short_units[unit_position(&unit)?] = template_checked(template)?;
+        }
+        Ok(())
+    }
+
+    /// Replaces some of the singular and plural units of time, given by unit name, e.g. `hour`.
+    pub fn set_units(&mut self, units: HashMap<String, (String, String)>) -> Result<()> {
+        for (unit, (singular, plural)) in units {
+            self.units[unit_position(&unit)?] =
+                (template_checked(singular)?, template_checked(plural)?);
+        }
+        Ok(())
+    }
+
+    /// Replaces the template for approximate durations.
+    pub fn set_about(&mut self, about: String) -> Result<()> {
+        self.about = template_checked(about)?;
+        Ok(())
+    }
+
+    /// Formats a number given with a dot as the decimal separator, e.g. by `format!`.
+    pub fn number(&self, text: &str) -> String {
+        localize_number(text, self.decimal_separator)
+    }
+
+    /// Formats an amount of a unit of time, e.g. `2 hours`.
+    ///
+    /// # Arguments
+    ///
+    /// * `unit` - The position of the unit in `TIME_UNITS`, e.g. `HOUR`.
+    /// * `amount` - The amount, already formatted.
+    /// * `plural` - Whether to use the plural form.
+    pub fn unit(&self, unit: usize, amount: &str, plural: bool) -> String {
+        let (singular, plural_form) = &self.units[unit];
+        let template = if plural { plural_form } else { singular };
+        template.replace("{}", amount)
+    }
+}
+
+/// Replaces the dot in a formatted number with a decimal separator.
+///
+/// When the decimal separator is a comma, commas used to group thousands become dots.
+pub fn localize_number(text: &str, decimal_separator: char) -> String {
+    match decimal_separator {
+        '.' => text.to_string(),
+        separator => text
+            .chars()
+            .map(|c| match c {
+                '.' => separator,
+                ',' if separator == ',' => '.',
+                c => c,
+            })
+            .collect(),
+    }
+}
+
+/// Returns the position of a unit of time in `TIME_UNITS`.
+fn unit_position(unit: &str) -> Result<usize> {
+    TIME_UNITS.iter().position(|&u| u == unit).ok_or_else(|| {
+        Error::InvalidLocale(format!(
+            "unknown unit of time {:?} (expected one of {})",
+            unit,
+            TIME_UNITS.join(", ")
+        ))
+    })
+}
+
+/// Checks that a template has a `{}` for the number.
+fn template_checked(template: String) -> Result<String> {
+    if !template.contains("{}") {
+        return Err(Error::InvalidLocale(format!(
+            "{:?} has no {{}} for the number",
+            template
+        )));
+    }
+    Ok(template)
+}
+
+/// The registered locales, by code.
+static LOCALES: LazyLock<RwLock<HashMap<String, Arc<Locale>>>> = LazyLock::new(|| {
+    let locales = [
+        (
+            "en",
+            Locale::new(
+                '.',
+                ["{}d", "{}h", "{}m", "{}s"],
+                ",",
+                [
+                    ("{} day", "{} days"),
+                    ("{} hour", "{} hours"),
+                    ("{} minute", "{} minutes"),
+                    ("{} second", "{} seconds"),
+                ],
+                "about {}",
+                "less than a minute",
+            ),
+        ),
+        (
+            "de",
+            Locale::new(
+                ',',
+                ["{} T.", "{} Std.", "{} Min.", "{} Sek."],
+                " ",
+                [
+                    ("{} Tag", "{} Tage"),
+                    ("{} Stunde", "{} Stunden"),
+                    ("{} Minute", "{} Minuten"),
+                    ("{} Sekunde", "{} Sekunden"),
+                ],
+                "etwa {}",
+                "weniger als eine Minute",
+            ),
+        ),
+        (
+            "fr",
+            Locale::new(
+                ',',
+                ["{} j", "{} h", "{} min", "{} s"],
+                " ",
+                [
+                    ("{} jour", "{} jours"),
+                    ("{} heure", "{} heures"),
+                    ("{} minute", "{} minutes"),
+                    ("{} seconde", "{} secondes"),
+                ],
+                "environ {}",
+                "moins d'une minute",
+            ),
+        ),
+        (
+            "es",
+            Locale::new(
+                ',',
+                ["{} d", "{} h", "{} min", "{} s"],
+                " ",
+                [
+                    ("{} día", "{} días"),
+                    ("{} hora", "{} horas"),
+                    ("{} minuto", "{} minutos"),
+                    ("{} segundo", "{} segundos"),
+                ],
+                "aproximadamente {}",
+                "menos de un minuto",
+            ),
+        ),
+        (
+            "ja",
+            Locale::new(
+                '.',
+                ["{}日", "{}時間", "{}分", "{}秒"],
+                "",
+                [
+                    ("{}日", "{}日"),
+                    ("{}時間", "{}時間"),
+                    ("{}分", "{}分"),
+                    ("{}秒", "{}秒"),
+                ],
+                "約{}",
+                "1分未満",
+            ),
+        ),
+    ];
+    let locales = locales
+        .into_iter()
+        .map(|(code, locale)| (code.to_string(), Arc::new(locale)))
+        .collect();
+    RwLock::new(locales)
+});
+
+/// The code of the global locale.
+static GLOBAL: LazyLock<RwLock<String>> = LazyLock::new(|| RwLock::new("en".to_string()));
+
+/// Looks up a registered locale.
+///
+/// A code with a region, e.g. `de-AT` or `de_AT`, falls back to its language if the region
+/// is not registered.
+pub fn find(code: &str) -> Result<Arc<Locale>> {
+    let locales = LOCALES.read().unwrap_or_else(|e| e.into_inner());
+    let language = code.split(['-', '_']).next().unwrap_or(code);
+    locales
+        .get(code)
+        .or_else(|| locales.get(language))
+        .cloned()
+        .ok_or_else(|| Error::UnknownLocale(code.to_string()))
+}
+
+/// Returns the locale with the given code, or the global locale if there is none.
+pub fn resolve(code: Option<&str>) -> Arc<Locale> {
+    let code = code.map_or_else(global, str::to_string);
+    // Locales cannot be unregistered, so a code that was found once is always found.
+    find(&code).expect("locale codes are checked when set")
+}
+
+/// Registers a locale, replacing any locale with the same code.
+pub fn register(code: &str, locale: Locale) {
+    let mut locales = LOCALES.write().unwrap_or_else(|e| e.into_inner());
+    locales.insert(code.to_string(), Arc::new(locale));
+}
+
+/// Returns the codes of the registered locales, sorted.
+pub fn codes() -> Vec<String> {
+    let locales = LOCALES.read().unwrap_or_else(|e| e.into_inner());
+    let mut codes: Vec<String> = locales.keys().cloned().collect();
+    codes.sort();
+    codes
+}
+
+/// Returns the code of the global locale.
+pub fn global() -> String {
+    GLOBAL.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Sets the global locale.
+pub fn set_global(code: &str) -> Result<()> {
+    find(code)?;
+    *GLOBAL.write().unwrap_or_else(|e| e.into_inner()) = code.to_string();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::duration::format_time;
+    use crate::enums::TimeFormat;
+
+    /// Asserts how a locale formats a number, 1.5 hours, and 30 seconds.
+    fn assert_formats(code: &str, number: &str, human: &str, about: &str, under: &str) {
+        let locale = find(code).unwrap();
+        let time = |seconds, format| format_time(seconds, format, &locale).to_string();
+        assert_eq!(locale.number("1,234.5"), number);
+        assert_eq!(time(5_400.0, TimeFormat::Human), human);
+        assert_eq!(time(5_400.0, TimeFormat::Approximate), about);
+        assert_eq!(time(30.0, TimeFormat::Approximate), under);
+    }
+
+    #[test]
+    fn formats_english() {
+        assert_formats(
+            "en",
+            "1,234.5",
+            "1h,30m",
+            "about 1½ hours",
+            "less than a minute",
+        );
+    }
+
+    #[test]
+    fn formats_german() {
+        let under = "weniger als eine Minute";
+        assert_formats("de", "1.234,5", "1 Std. 30 Min.", "etwa 1½ Stunden", under);
+    }
+
+    #[test]
+    fn formats_french() {
+        let under = "moins d'une minute";
+        assert_formats("fr", "1.234,5", "1 h 30 min", "environ 1½ heures", under);
+    }
+
+    #[test]
+    fn formats_spanish() {
+        let under = "menos de un minuto";
+        assert_formats(
+            "es",
+            "1.234,5",
+            "1 h 30 min",
+            "aproximadamente 1½ horas",
+            under,
+        );
+    }
+
+    #[test]
+    fn formats_japanese() {
+        assert_formats("ja", "1,234.5", "1時間30分", "約1½時間", "1分未満");
+    }
+
+    #[test]
+    fn uses_singular_and_plural_units() {
+        let locale = find("de").unwrap();
+        assert_eq!(locale.unit(HOUR, "1", false), "1 Stunde");
+        assert_eq!(locale.unit(HOUR, "2", true), "2 Stunden");
+        let time = |seconds| format_time(seconds, TimeFormat::Approximate, &locale).to_string();
+        assert_eq!(time(86_400.0 * 3.0), "etwa 3 Tage");
+        assert_eq!(time(60.0), "etwa 1 Minute");
+    }
+
+    #[test]
+    fn falls_back_to_the_language_of_a_region() {
+        assert_eq!(find("de-AT").unwrap(), find("de").unwrap());
+        assert_eq!(find("fr_CA").unwrap(), find("fr").unwrap());
+        assert!(matches!(find("xx-YY"), Err(Error::UnknownLocale(code)) if code == "xx-YY"));
+        assert!(matches!(find("pt"), Err(Error::UnknownLocale(_))));
+    }
+
+    #[test]
+    fn prefers_a_registered_region() {
+        let mut swiss = (*find("de").unwrap()).clone();
+        swiss.decimal_separator = '.';
+        register("xq-CH", swiss.clone());
+        assert_eq!(*find("xq-CH").unwrap(), swiss);
+        assert!(find("xq-AT").is_err());
+        register("xq", (*find("de").unwrap()).clone());
+        assert_eq!(find("xq-AT").unwrap(), find("de").unwrap());
+        assert_eq!(*find("xq-CH").unwrap(), swiss);
+    }
+
+    #[test]
+    fn localizes_numbers() {
+        assert_eq!(localize_number("1,234.5", '.'), "1,234.5");
+        assert_eq!(localize_number("1,234.5", ','), "1.234,5");
+        assert_eq!(localize_number("-0.25", '٫'), "-0٫25");
+    }
+
+    #[test]
+    fn checks_replaced_words() {
+        let mut locale = (*find("en").unwrap()).clone();
+        locale
+            .set_units(HashMap::from([(
+                "hour".to_string(),
+                ("{} hora".to_string(), "{} horas".to_string()),
+            )]))
+            .unwrap();
+        assert_eq!(locale.unit(HOUR, "2", true), "2 horas");
+        assert_eq!(locale.unit(MINUTE, "2", true), "2 minutes");
+        let week = HashMap::from([("week".to_string(), "{}w".to_string())]);
+        assert!(matches!(
+            locale.set_short_units(week),
+            Err(Error::InvalidLocale(_))
+        ));
+        assert!(matches!(
+            locale.set_about("about".to_string()),
+            Err(Error::InvalidLocale(_))
+        ));
+    }
+}
@@ -1,8 +1,24 @@
+#[cfg(feature = "python")]
+use pyo3::IntoPyObjectExt;
+#[cfg(feature = "python")]
+use pyo3::exceptions::PyZeroDivisionError;
+#[cfg(feature = "python")]
 use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3::pyclass::CompareOp;
+#[cfg(feature = "python")]
+use pyo3::types::{PyFloat, PyInt};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
 use std::fmt;
 
+use crate::error::{Error, Result};
+#[cfg(feature = "python")]
+use crate::locale;
+use crate::locale::localize_number;
+use crate::units::{Quantity, Unit, convert, widen};
+
 /// Represents a measurement with a value, units, and precision.
-#[pyclass]
+#[cfg_attr(feature = "python", pyclass)]
 #[derive(Debug, Clone)]
 pub struct Measurement {
     /// The value of the measurement.
@@ -11,20 +27,118 @@ pub struct Measurement {
     pub units: String,
     /// The number of decimal places to display.
     pub decimals: usize,
+    /// The decimal separator to display.
+    pub decimal_separator: char,
 }
 
 impl Measurement {
     /// A private helper method to format the measurement as a string.
     fn format_measurement(&self) -> String {
+        self.with_units(&self.format_value())
+    }
+
+    /// Formats the value with the number of decimals and the decimal separator, without units.
+    pub fn format_value(&self) -> String {
         let formatted_value = format!("{:.precision$}", self.value, precision = self.decimals);
+        localize_number(&formatted_value, self.decimal_separator)
+    }
+
+    /// Appends the units to a formatted value.
+    ///
+    /// If the unit is percent, the value and unit are joined without a space between them.
+    fn with_units(&self, formatted_value: &str) -> String {
         if self.units == "%" {
             format!("{}{}", formatted_value, self.units)
         } else {
             format!("{} {}", formatted_value, self.units)
         }
     }
+
+    /// Returns the value of another measurement in the units of this one.
+    pub fn value_of(&self, other: &Measurement) -> Result<f64> {
+        convert(other.value as f64, &other.units, &self.units)
+    }
+
+    /// Returns the value in the base unit of its quantity, e.g. in Wh for a measurement in kWh,
+    /// at the precision measurements are compared at. Unknown units are left as they are.
+    pub fn base_value(&self) -> f32 {
+        Unit::find(&self.units)
+            .and_then(|unit| convert(self.value as f64, &self.units, unit.quantity.base()))
+            .map_or(self.value, |value| value as f32)
+    }
+
+    /// Returns whether another measurement is equal to this one, comparing both in base units.
+    /// Measurements of different quantities are never equal.
+    pub fn equals(&self, other: &Measurement) -> bool {
+        self.value_of(other).is_ok() && self.base_value() == other.base_value()
+    }
+
+    /// Returns whether a number is equal to the value in base units.
+    pub fn equals_number(&self, number: f64) -> bool {
+        self.base_value() == number as f32
+    }
+
+    /// Returns the number whose hash is the hash of the measurement. Measurements that are
+    /// equal to each other or to a number have the same one.
+    pub fn hash_value(&self) -> f64 {
+        widen(self.base_value())
+    }
+
+    /// Returns the value as a Python float, without f32 rounding noise.
+    pub fn raw_value(&self) -> f64 {
+        widen(self.value)
+    }
+
+    /// Converts the measurement to other units of the same quantity, adjusting the number of
+    /// decimals so the precision stays the same, e.g. one decimal in Wh becomes four in kWh.
+    pub fn convert_to(&self, units: &str) -> Result<Measurement> {
+        let symbol = if units == self.units {
+            units
+        } else {
+            Unit::find(units)?.symbol
+        };
+        let value = convert(self.value as f64, &self.units, symbol)?;
+        // Keep the same absolute precision, e.g. 40.0 Wh is 0.0400 kWh rather than 0.0 kWh.
+        let size = convert(1.0, &self.units, symbol)? - convert(0.0, &self.units, symbol)?;
+        let decimals = (self.decimals as f64 - size.log10().round()).max(0.0) as usize;
+        Ok(Measurement {
+            value: value as f32,
+            units: symbol.to_string(),
+            decimals,
+            ..self.clone()
+        })
+    }
+
+    /// Returns a measurement with the same units and precision and a different value.
+    pub fn with_value(&self, value: f64) -> Measurement {
+        Measurement {
+            value: value as f32,
+            ..self.clone()
+        }
+    }
+
+    /// Returns the value of another measurement in the units of this one, for adding to or
+    /// subtracting from this one.
+    ///
+    /// Temperatures can only be combined in the same units, since converting a temperature
+    /// difference is not the same as converting a temperature.
+    pub fn addend(&self, other: &Measurement) -> Result<f64> {
+        let is_temperature = |units: &str| {
+            Unit::find(units).is_ok_and(|unit| unit.quantity == Quantity::Temperature)
+        };
+        if self.units != other.units
+            && (is_temperature(&self.units) || is_temperature(&other.units))
+        {
+            return Err(Error::IncompatibleUnits(
+                self.units.clone(),
+                other.units.clone(),
+            ));
+        }
+        self.value_of(other)
+    }
 }
 
+#[cfg(feature = "python")]
 #[pymethods]
 impl Measurement {
     /// Creates a new `Measurement` instance.
@@ -34,19 +148,23 @@ impl Measurement {
     /// * `value` - The value of the measurement.
     /// * `units` - The units of the measurement.
     /// * `decimals` - The number of decimal places to display.
+    ///
+    /// The measurement is displayed with the decimal separator of the global locale.
     #[new]
     pub fn new(value: f32, units: String, decimals: usize) -> Self {
         Self {
             value,
             units,
             decimals,
+            decimal_separator: locale::resolve(None).decimal_separator,
         }
     }
 
-    /// Returns the value of the measurement.
+    /// Returns the value of the measurement, as read and without rounding to the number of
+    /// decimals displayed.
     #[getter]
-    fn value(&self) -> PyResult<f32> {
-        Ok(self.value)
+    fn value(&self) -> PyResult<f64> {
+        Ok(self.raw_value())
     }
 
     /// Returns the units of the measurement.
@@ -62,6 +180,149 @@ impl Measurement {
         Ok(self.format_measurement())
     }
 
+    /// Converts the measurement to other units of the same quantity.
+    ///
+    /// # Arguments
+    ///
+    /// * `units` - The units to convert to, e.g. `mWh`, `J`, `kW`, `mV` or `K`.
+    ///
+    /// # Returns
+    ///
+    /// A new `Measurement` with the converted value. The number of decimals is adjusted so the
+    /// precision stays the same, e.g. one decimal in Wh becomes four in kWh.
+    fn to(&self, units: &str) -> PyResult<Measurement> {
+        Ok(self.convert_to(units)?)
+    }
+
+    /// Returns the value of the measurement, for `float()`.
+    fn __float__(&self) -> f64 {
+        self.raw_value()
+    }
+
+    /// Compares the measurement with a number or with another measurement.
+    ///
+    /// Equality is decided in base units, so that equal measurements hash the same: 1 kWh
+    /// equals 1000 Wh and the number 1000. Ordering a measurement with a number compares the
+    /// value, and with another measurement converts it to the units of this one first.
+    /// Measurements of different quantities are never equal and cannot be ordered.
+    fn __richcmp__(&self, other: &Bound<'_, PyAny>, op: CompareOp) -> PyResult<PyObject> {
+        let py = other.py();
+        let equal = |equal: bool| match op {
+            CompareOp::Ne => (!equal).into_py_any(py),
+            _ => equal.into_py_any(py),
+        };
+        let other = if let Ok(other) = other.downcast::<Measurement>() {
+            let other = other.borrow();
+            if matches!(op, CompareOp::Eq | CompareOp::Ne) {
+                return equal(self.equals(&other));
+            }
+            self.value_of(&other)?
+        } else if let Ok(number) = other.extract::<f64>() {
+            if matches!(op, CompareOp::Eq | CompareOp::Ne) {
+                return equal(self.equals_number(number));
+            }
+            number
+        } else {
+            return Ok(py.NotImplemented());
+        };
+        // Compare at the precision of the value, so that e.g. `Measurement(88.2, ...) <= 88.2`.
+        let ordering = self.value.partial_cmp(&(other as f32));
+        ordering
+            .is_some_and(|ordering| op.matches(ordering))
+            .into_py_any(py)
+    }
+
+    /// Returns the hash of the value in base units, the same as the hash of a number equal to
+    /// the measurement.
+    fn __hash__(&self, py: Python<'_>) -> PyResult<isize> {
+        PyFloat::new(py, self.hash_value()).hash()
+    }
+
+    /// Adds another measurement, converted to the units of this one.
+    fn __add__(&self, other: PyRef<'_, Measurement>) -> PyResult<Measurement> {
+        Ok(self.with_value(self.value as f64 + self.addend(&other)?))
+    }
+
+    /// Supports `sum()` over measurements, which starts by adding to `0`.
+    fn __radd__(&self, other: &Bound<'_, PyAny>) -> PyResult<PyObject> {
+        let py = other.py();
+        if other
+            .downcast::<PyInt>()
+            .is_ok_and(|n| n.extract::<i64>().is_ok_and(|n| n == 0))
+        {
+            return self.clone().into_py_any(py);
+        }
+        Ok(py.NotImplemented())
+    }
+
+    /// Subtracts another measurement, converted to the units of this one.
+    fn __sub__(&self, other: PyRef<'_, Measurement>) -> PyResult<Measurement> {
+        Ok(self.with_value(self.value as f64 - self.addend(&other)?))
+    }
+
+    /// Scales the measurement by a number.
+    fn __mul__(&self, factor: f64) -> Measurement {
+        self.with_value(self.value as f64 * factor)
+    }
+
+    /// Scales the measurement by a number.
+    fn __rmul__(&self, factor: f64) -> Measurement {
+        self.__mul__(factor)
+    }
+
+    /// Divides the measurement by a number, or by another measurement of the same quantity.
+    ///
+    /// Dividing by a number returns a measurement in the same units. Dividing by a
+    /// measurement returns the ratio as a number, e.g. `battery.energy / battery.energy_full`.
+    fn __truediv__(&self, other: &Bound<'_, PyAny>) -> PyResult<PyObject> {
+        let py = other.py();
+        let (divisor, ratio) = if let Ok(other) = other.downcast::<Measurement>() {
+            (self.value_of(&other.borrow())?, true)
+        } else if let Ok(number) = other.extract::<f64>() {
+            (number, false)
+        } else {
+            return Ok(py.NotImplemented());
+        };
+        if divisor == 0.0 {
+            return Err(PyZeroDivisionError::new_err("division by zero"));
+        }
+        let value = self.value as f64 / divisor;
+        if ratio {
+            value.into_py_any(py)
+        } else {
+            self.with_value(value).into_py_any(py)
+        }
+    }
+
+    /// Formats the measurement for `format()` and f-strings.
+    ///
+    /// The spec is a Python format spec. The sign, grouping, precision and type format the
+    /// value the way they format a float, and the fill, alignment and width pad the whole
+    /// string, units included. Without a precision or type, the value is shown with the
+    /// measurement's own number of decimals. A trailing `~` leaves out the units.
+    ///
+    /// # Examples
+    ///
+    /// `f"{battery.voltage:.3f}"` gives `12.345 V`, `f"{battery.energy:.0f~}"` gives `52`, and
+    /// `f"{battery.percent:>6}"` gives ` 88.2%`.
+    fn __format__(&self, py: Python<'_>, spec: &str) -> PyResult<String> {
+        let spec = FormatSpec::parse(spec);
+        let value = if spec.number.is_empty() {
+            format!("{:.precision$}", self.value, precision = self.decimals)
+        } else {
+            let value = self.raw_value();
+            let format = py.import("builtins")?.getattr("format")?;
+            format.call1((value, &spec.number))?.extract()?
+        };
+        let value = localize_number(&value, self.decimal_separator);
+        let text = if spec.value_only {
+            value
+        } else {
+            self.with_units(&value)
+        };
+        Ok(spec.pad(text))
+    }
+
     /// Returns a string representation of the measurement.
     ///
     /// This method is used by Python's `repr()` function.
@@ -70,8 +331,228 @@ impl Measurement {
     }
 }
 
+/// Serializes as `{"value": ..., "units": ...}`, with the value as read.
+impl Serialize for Measurement {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut fields = serializer.serialize_struct("Measurement", 2)?;
+        fields.serialize_field("value", &self.raw_value())?;
+        fields.serialize_field("units", &self.units)?;
+        fields.end()
+    }
+}
+
 impl fmt::Display for Measurement {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.format_measurement())
     }
 }
+
+/// A Python format spec, split into the part that formats the value and the part that pads
+/// the whole string.
+#[cfg(feature = "python")]
+#[derive(Debug, Default, PartialEq)]
+struct FormatSpec {
+    /// The fill character for padding.
+    fill: char,
+    /// The alignment: `<`, `>` or `^`.
+    align: char,
+    /// The minimum width of the whole string.
+    width: usize,
+    /// The spec passed to Python to format the value.
+    number: String,
+    /// Whether to leave out the units, asked for with a trailing `~`.
+    value_only: bool,
+}
+
+#[cfg(feature = "python")]
+impl FormatSpec {
+    /// Splits a spec of the form `[[fill]align][sign][z][#][0][width][grouping][.precision][type]`,
+    /// optionally followed by `~`. Python reports specs that are not valid when the value is
+    /// formatted.
+    ///
+    /// With `=` alignment or the `0` flag, the padding goes between the sign and the digits, so
+    /// the fill, alignment and width are left to Python and apply to the value alone.
+    fn parse(spec: &str) -> Self {
+        let (spec, value_only) = match spec.strip_suffix('~') {
+            Some(spec) => (spec, true),
+            None => (spec, false),
+        };
+        let chars: Vec<char> = spec.chars().collect();
+        let is_align = |c: &char| matches!(c, '<' | '>' | '^' | '=');
+        let (fill, align, rest) = match chars.as_slice() {
+            [fill, align, rest @ ..] if is_align(align) => (*fill, *align, rest),
+            [align, rest @ ..] if is_align(align) => (' ', *align, rest),
+            rest => (' ', '>', rest),
+        };
+        let start = rest
+            .iter()
+            .take_while(|c| matches!(c, '+' | '-' | ' ' | 'z' | '#'))
+            .count();
+        let zero = rest.get(start) == Some(&'0');
+        let digits_start = start + zero as usize;
+        let digits = rest[digits_start..]
+            .iter()
+            .take_while(|c| c.is_ascii_digit())
+            .count();
+        let width_text: String = rest[digits_start..digits_start + digits].iter().collect();
+        let width = width_text.parse().unwrap_or(0);
+        let flags: String = rest[..start].iter().collect();
+        let tail: String = rest[digits_start + digits..].iter().collect();
+
+        if align == '=' || zero {
+            let alignment = if align == '=' {
+                format!("{}=", fill)
+            } else {
+                String::new()
+            };
+            let zero = if zero { "0" } else { "" };
+            FormatSpec {
+                number: format!("{}{}{}{}{}", alignment, flags, zero, width_text, tail),
+                value_only,
+                ..FormatSpec::default()
+            }
+        } else {
+            FormatSpec {
+                fill,
+                align,
+                width,
+                number: format!("{}{}", flags, tail),
+                value_only,
+            }
+        }
+    }
+
+    /// Pads a string to the width.
+    fn pad(&self, text: String) -> String {
+        let padding = self.width.saturating_sub(text.chars().count());
+        if padding == 0 {
+            return text;
+        }
+        let fill = |count: usize| self.fill.to_string().repeat(count);
+        match self.align {
+            '<' => format!("{}{}", text, fill(padding)),
+            '^' => format!(
+                "{}{}{}",
+                fill(padding / 2),
+                text,
+                fill(padding - padding / 2)
+            ),
+            _ => format!("{}{}", fill(padding), text),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a measurement with one decimal.
+    fn measurement(value: f32, units: &str) -> Measurement {
+        Measurement {
+            value,
+            units: units.to_string(),
+            decimals: 1,
+            decimal_separator: '.',
+        }
+    }
+
+    #[test]
+    fn converts_keeping_the_precision() {
+        let kwh = measurement(40.0, "Wh").convert_to("kWh").unwrap();
+        assert_eq!(
+            (kwh.value, kwh.units.as_str(), kwh.decimals),
+            (0.04, "kWh", 4)
+        );
+        let mwh = measurement(40.0, "Wh").convert_to("mWh").unwrap();
+        assert_eq!((mwh.value, mwh.decimals), (40000.0, 0));
+        let degf = measurement(30.0, "°C").convert_to("F").unwrap();
+        assert_eq!(
+            (degf.value, degf.units.as_str(), degf.decimals),
+            (86.0, "°F", 1)
+        );
+        assert!(measurement(1.0, "W").convert_to("Wh").is_err());
+        assert!(measurement(1.0, "W").convert_to("furlongs").is_err());
+    }
+
+    #[test]
+    fn combines_in_the_units_of_the_left() {
+        let wh = measurement(1000.0, "Wh");
+        assert_eq!(wh.addend(&measurement(0.5, "kWh")).unwrap(), 500.0);
+        assert!(wh.addend(&measurement(1.0, "W")).is_err());
+        let degc = measurement(30.0, "°C");
+        assert_eq!(degc.addend(&measurement(2.0, "°C")).unwrap(), 2.0);
+        assert!(degc.addend(&measurement(2.0, "K")).is_err());
+    }
+
+    #[cfg(feature = "python")]
+    #[test]
+    fn parses_format_specs() {
+        let spec = |fill, align, width, number: &str, value_only| FormatSpec {
+            fill,
+            align,
+            width,
+            number: number.to_string(),
+            value_only,
+        };
+        assert_eq!(FormatSpec::parse(""), spec(' ', '>', 0, "", false));
+        assert_eq!(FormatSpec::parse(">6"), spec(' ', '>', 6, "", false));
+        assert_eq!(FormatSpec::parse("*^9.1f"), spec('*', '^', 9, ".1f", false));
+        assert_eq!(
+            FormatSpec::parse("<+8,.2f~"),
+            spec(' ', '<', 8, "+,.2f", true)
+        );
+        assert_eq!(FormatSpec::parse("~"), spec(' ', '>', 0, "", true));
+        // The padding of `=` alignment and the `0` flag is left to Python.
+        let python = |number: &str| spec('\0', '\0', 0, number, false);
+        assert_eq!(FormatSpec::parse("0=10.2f"), python("0=10.2f"));
+        assert_eq!(FormatSpec::parse("+08.3f"), python("+08.3f"));
+        // Specs that are not valid are passed on, for Python to report.
+        assert_eq!(FormatSpec::parse("10q"), spec(' ', '>', 10, "q", false));
+        assert_eq!(FormatSpec::parse("<<<"), spec('<', '<', 0, "<", false));
+    }
+
+    #[cfg(feature = "python")]
+    #[test]
+    fn pads_to_the_width() {
+        let pad = |spec: &str| FormatSpec::parse(spec).pad("5%".to_string());
+        assert_eq!(pad(">6"), "    5%");
+        assert_eq!(pad("<6"), "5%    ");
+        assert_eq!(pad("*^7"), "**5%***");
+        assert_eq!(pad("1"), "5%");
+        assert_eq!(pad("0=10"), "5%");
+    }
+
+    #[test]
+    fn equal_in_different_units() {
+        let (kwh, wh) = (measurement(1.0, "kWh"), measurement(1000.0, "Wh"));
+        assert!(kwh.equals(&wh) && wh.equals(&kwh));
+        assert_eq!(kwh.hash_value(), wh.hash_value());
+        assert!(measurement(50.0, "°F").equals(&measurement(10.0, "°C")));
+        assert!(!measurement(1.0, "Wh").equals(&measurement(1.0, "W")));
+    }
+
+    #[test]
+    fn equal_to_a_number_in_base_units() {
+        let mwh = measurement(1000.0, "mWh");
+        assert!(mwh.equals_number(1.0));
+        assert!(!mwh.equals_number(1000.0));
+        assert_eq!(mwh.hash_value(), 1.0);
+    }
+
+    #[test]
+    fn hash_is_the_hash_of_an_equal_float() {
+        let cases = [
+            (88.2, "%"),
+            (12.345, "V"),
+            (-40.0, "°F"),
+            (0.0, "kWh"),
+            (3.5, "xyz"),
+        ];
+        for (value, units) in cases {
+            let m = measurement(value, units);
+            if m.equals_number(m.raw_value()) {
+                assert_eq!(m.hash_value(), m.raw_value(), "{}", m);
+            }
+        }
+    }
+}
@@ -0,0 +1,325 @@
+use pyo3::prelude::*;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::battery::Battery;
+use crate::enums::BatteryState;
+use crate::source::{Reading, Source};
+
+/// A change detected by a `Watcher`.
+#[derive(Debug, Clone, PartialEq)]
+enum Event {
+    /// The state changed from the first value to the second.
+    StateChange(BatteryState, BatteryState),
+    /// The percentage crossed a threshold, downwards if `below` is true.
+    Threshold {
+        threshold: f32,
+        below: bool,
+        percent: f32,
+    },
+    /// The battery was removed (`false`) or plugged back in (`true`).
+    Presence(bool),
+}
+
+/// Tracks successive readings and reports the changes between them.
+#[derive(Debug)]
+struct Watcher {
+    /// The thresholds to watch, with whether the percentage is currently below each one.
+    thresholds: Vec<(f32, Option<bool>)>,
+    /// How far above a threshold the percentage must rise before it counts as back above.
+    hysteresis: f32,
+    /// The state from the last successful reading.
+    state: Option<BatteryState>,
+    /// Whether the last reading succeeded.
+    present: Option<bool>,
+}
+
+impl Watcher {
+    /// Creates a watcher for the given thresholds.
+    fn new(thresholds: Vec<f32>, hysteresis: f32) -> Self {
+        Watcher {
+            thresholds: thresholds.into_iter().map(|t| (t, None)).collect(),
+            hysteresis,
+            state: None,
+            present: None,
+        }
+    }
+
+    /// Records the outcome of a read and returns the changes since the previous one.
+    ///
+    /// The first reading only sets the baseline and never produces events.
+    fn update(&mut self, reading: Option<&Reading>) -> Vec<Event> {
+        let mut events = Vec::new();
+        let present = reading.is_some();
+        if self
+            .present
+            .is_some_and(|was_present| was_present != present)
+        {
+            events.push(Event::Presence(present));
+        }
+        self.present = Some(present);
+
+        let Some(reading) = reading else {
+            return events;
+        };
+
+        if let Some(old) = self.state.filter(|&old| old != reading.state) {
+            events.push(Event::StateChange(old, reading.state));
+        }
+        self.state = Some(reading.state);
+
+        let percent = reading.state_of_charge;
+        for (threshold, below) in self.thresholds.iter_mut() {
+            let now_below = match *below {
+                Some(true) => percent < *threshold + self.hysteresis,
+                _ => percent < *threshold,
+            };
+            if below.is_some_and(|was_below| was_below != now_below) {
+                events.push(Event::Threshold {
+                    threshold: *threshold,
+                    below: now_below,
+                    percent,
+                });
+            }
+            *below = Some(now_below);
+        }
+        events
+    }
+}
+
+/// The Python callbacks registered on a monitor.
+#[derive(Debug, Default)]
+struct Callbacks {
+    /// Called with the old and new state.
+    state_change: Vec<PyObject>,
+    /// Called with the threshold, the direction and the percentage.
+    threshold: Vec<PyObject>,
+    /// Called with whether the battery is present.
+    presence_change: Vec<PyObject>,
+}
+
+impl Callbacks {
+    /// Calls the callbacks registered for an event.
+    ///
+    /// Exceptions raised by a callback are reported through `sys.unraisablehook` so that one
+    /// failing callback does not stop the monitor.
+    fn dispatch(callbacks: &Mutex<Callbacks>, py: Python<'_>, event: &Event) {
+        // Copy the callbacks out and release the lock before building Python objects, which can
+        // switch to another thread that is waiting for the lock.
+        let targets: Vec<PyObject> = {
+            let callbacks = callbacks.lock().unwrap_or_else(|e| e.into_inner());
+            let targets = match event {
+                Event::StateChange(..) => &callbacks.state_change,
+                Event::Threshold { .. } => &callbacks.threshold,
+                Event::Presence(_) => &callbacks.presence_change,
+            };
+            targets.iter().map(|cb| cb.clone_ref(py)).collect()
+        };
+        let args = match event {
+            Event::StateChange(old, new) => (*old, *new).into_pyobject(py),
+            Event::Threshold {
+                threshold,
+                below,
+                percent,
+            } => (*threshold, if *below { "below" } else { "above" }, *percent).into_pyobject(py),
+            Event::Presence(present) => (*present,).into_pyobject(py),
+        };
+        let args = match args {
+            Ok(args) => args,
+            Err(e) => return e.write_unraisable(py, None),
+        };
+        for callback in targets {
+            if let Err(e) = callback.bind(py).call1(args.clone()) {
+                e.write_unraisable(py, Some(callback.bind(py)));
+            }
+        }
+    }
+}
+
+/// Watches a battery on a background thread and calls Python callbacks when it changes.
+///
+/// The monitor reads the battery on its own, independently of the `Battery` it was created
+/// from. Callbacks are called on the monitor's thread.
+#[pyclass]
+#[derive(Debug)]
+pub struct BatteryMonitor {
+    /// The source to read while the monitor is stopped. The background thread takes it when
+    /// the monitor starts and puts it back when it finishes.
+    source: Arc<Mutex<Option<Box<dyn Source>>>>,
+    /// The percentages to report crossings of.
+    thresholds: Vec<f32>,
+    /// How far above a threshold the percentage must rise before it counts as back above.
+    hysteresis: f32,
+    /// The interval between reads.
+    interval: Duration,
+    /// The registered callbacks.
+    callbacks: Arc<Mutex<Callbacks>>,
+    /// Stops the background thread when sent to or dropped.
+    stop: Mutex<Option<Sender<()>>>,
+    /// The background thread, while running.
+    handle: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl BatteryMonitor {
+    /// Locks a mutex, ignoring poisoning since the protected values stay consistent.
+    fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+        mutex.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[pymethods]
+impl BatteryMonitor {
+    /// Creates a new monitor for a battery. The monitor does not run until `start` is called.
+    ///
+    /// # Arguments
+    ///
+    /// * `battery` - The battery to watch.
+    /// * `interval` - The interval between reads in milliseconds, which must be positive
+    ///   (default: 1000).
+    /// * `thresholds` - The percentages to report crossings of (default: none).
+    /// * `hysteresis` - How many percentage points the percentage must rise above a threshold
+    ///   before it counts as back above, so a value hovering at a threshold does not report a
+    ///   crossing on every read (default: 1.0).
+    #[new]
+    #[pyo3(signature = (battery, interval=1000, thresholds=Vec::new(), hysteresis=1.0))]
+    fn new(
+        py: Python<'_>,
+        battery: &Battery,
+        interval: u64,
+        thresholds: Vec<f32>,
+        hysteresis: f32,
+    ) -> PyResult<Self> {
+        if interval == 0 {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "interval must be positive",
+            ));
+        }
+        if hysteresis < 0.0 {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "hysteresis must not be negative",
+            ));
+        }
+        Ok(BatteryMonitor {
+            source: Arc::new(Mutex::new(Some(
+                py.allow_threads(|| battery.open_source())?,
+            ))),
+            thresholds,
+            hysteresis,
+            interval: Duration::from_millis(interval),
+            callbacks: Arc::new(Mutex::new(Callbacks::default())),
+            stop: Mutex::new(None),
+            handle: Mutex::new(None),
+        })
+    }
+
+    /// Registers a callback called as `callback(old_state, new_state)` when the state changes.
+    fn on_state_change(&self, callback: PyObject) -> PyResult<()> {
+        BatteryMonitor::lock(&self.callbacks)
+            .state_change
+            .push(callback);
+        Ok(())
+    }
+
+    /// Registers a callback called as `callback(threshold, direction, percent)` when the
+    /// percentage crosses a threshold. `direction` is `"below"` or `"above"`.
+    fn on_threshold(&self, callback: PyObject) -> PyResult<()> {
+        BatteryMonitor::lock(&self.callbacks)
+            .threshold
+            .push(callback);
+        Ok(())
+    }
+
+    /// Registers a callback called as `callback(present)` when the battery is removed
+    /// (`present` is `False`) or plugged back in (`present` is `True`).
+    fn on_presence_change(&self, callback: PyObject) -> PyResult<()> {
+        BatteryMonitor::lock(&self.callbacks)
+            .presence_change
+            .push(callback);
+        Ok(())
+    }
+
+    /// Starts watching the battery on a background thread.
+    fn start(&self) -> PyResult<()> {
+        let mut source = BatteryMonitor::lock(&self.source).take().ok_or_else(|| {
+            PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Monitor is already running")
+        })?;
+        let (stop, stopped) = mpsc::channel();
+        let callbacks = self.callbacks.clone();
+        let slot = self.source.clone();
+        let interval = self.interval;
+        let mut watcher = Watcher::new(self.thresholds.clone(), self.hysteresis);
+
+        let handle = thread::spawn(move || {
+            loop {
+                let reading = source.read().ok();
+                let events = watcher.update(reading.as_ref());
+                if !events.is_empty() {
+                    Python::with_gil(|py| {
+                        for event in &events {
+                            Callbacks::dispatch(&callbacks, py, event);
+                        }
+                    });
+                }
+                match stopped.recv_timeout(interval) {
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    _ => break,
+                }
+            }
+            *BatteryMonitor::lock(&slot) = Some(source);
+        });
+
+        *BatteryMonitor::lock(&self.stop) = Some(stop);
+        *BatteryMonitor::lock(&self.handle) = Some(handle);
+        Ok(())
+    }
+
+    /// Stops watching the battery and waits for the background thread to finish.
+    ///
+    /// The monitor can be started again afterwards. Does nothing if the monitor is not
+    /// running. When called from a callback, the thread cannot wait for itself, so the monitor
+    /// stops once the callbacks for the current read have returned.
+    fn stop(&self, py: Python<'_>) -> PyResult<()> {
+        BatteryMonitor::lock(&self.stop).take();
+        let Some(handle) = BatteryMonitor::lock(&self.handle).take() else {
+            return Ok(());
+        };
+        if handle.thread().id() == thread::current().id() {
+            return Ok(());
+        }
+        // The thread may be waiting for the GIL to call a callback, so release it while joining.
+        py.allow_threads(|| handle.join()).map_err(|_| {
+            PyErr::new::<pyo3::exceptions::PyRuntimeError, _>("Monitor thread panicked")
+        })
+    }
+
+    /// Returns whether the monitor is running.
+    #[getter]
+    fn running(&self) -> PyResult<bool> {
+        Ok(BatteryMonitor::lock(&self.handle).is_some())
+    }
+
+    /// Returns the interval between reads in milliseconds.
+    #[getter]
+    fn interval(&self) -> PyResult<u64> {
+        Ok(self.interval.as_millis() as u64)
+    }
+
+    /// Starts the monitor when used as a context manager.
+    fn __enter__(slf: PyRef<'_, Self>) -> PyResult<PyRef<'_, Self>> {
+        slf.start()?;
+        Ok(slf)
+    }
+
+    /// Stops the monitor at the end of a `with` block.
+    fn __exit__(
+        &self,
+        py: Python<'_>,
+        _exc_type: PyObject,
+        _exc_value: PyObject,
+        _traceback: PyObject,
+    ) -> PyResult<()> {
+        self.stop(py)
+    }
+}
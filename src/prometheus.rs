@@ -0,0 +1,621 @@
+//! Exporting battery information as Prometheus metrics.
+//!
+//! The metrics are in the Prometheus text exposition format. They can be served over HTTP by a
+//! `MetricsServer`, or written with `write_textfile` to a file read by the node_exporter
+//! textfile collector. Every series is labelled with the battery index, vendor, model and
+//! serial number, and values are in base units whatever the display settings are.
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::display::DisplaySettings;
+use crate::enums::{BatteryState, TempUnit, TimeFormat};
+use crate::error::{Error, Result};
+use crate::measurement::Measurement;
+use crate::snapshot::BatterySnapshot;
+use crate::source::Backend;
+use crate::units::{convert, widen};
+
+/// The content type of the text exposition format.
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// How long the server gives a client to send its request and read the response.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How many connections the server answers at once. Connections beyond this are closed.
+const MAX_CONNECTIONS: usize = 8;
+
+/// The longest request line or header the server reads, in bytes.
+const MAX_LINE: u64 = 8192;
+
+/// The most headers the server reads from a request.
+const MAX_HEADERS: usize = 100;
+
+/// A gauge exported for every battery that has a value for it.
+struct Gauge {
+    /// The name of the metric.
+    name: &'static str,
+    /// The description of the metric.
+    help: &'static str,
+    /// Returns the value of the metric for a battery.
+    value: fn(&BatterySnapshot) -> Option<f64>,
+}
+
+/// The gauges, in the order they are exported. The state is exported after them.
+const GAUGES: [Gauge; 10] = [
+    Gauge {
+        name: "batteryinfo_percent",
+        help: "The percentage of the battery that is full.",
+        value: |snapshot| Some(base(&snapshot.percent, "%")),
+    },
+    Gauge {
+        name: "batteryinfo_energy_wh",
+        help: "The current energy of the battery in watt-hours.",
+        value: |snapshot| Some(base(&snapshot.energy, "Wh")),
+    },
+    Gauge {
+        name: "batteryinfo_energy_full_wh",
+        help: "The energy of the battery when full in watt-hours.",
+        value: |snapshot| Some(base(&snapshot.energy_full, "Wh")),
+    },
+    Gauge {
+        name: "batteryinfo_energy_full_design_wh",
+        help: "The design energy of the battery in watt-hours.",
+        value: |snapshot| Some(base(&snapshot.energy_full_design, "Wh")),
+    },
+    Gauge {
+        name: "batteryinfo_energy_rate_w",
+        help: "The rate the battery is charging or discharging at in watts.",
+        value: |snapshot| Some(base(&snapshot.energy_rate, "W")),
+    },
+    Gauge {
+        name: "batteryinfo_voltage_v",
+        help: "The voltage of the battery in volts.",
+        value: |snapshot| Some(base(&snapshot.voltage, "V")),
+    },
+    Gauge {
+        name: "batteryinfo_temperature_celsius",
+        help: "The temperature of the battery in degrees Celsius.",
+        value: |snapshot| {
+            snapshot
+                .temperature
+                .as_ref()
+                .map(|temperature| base(temperature, "°C"))
+        },
+    },
+    Gauge {
+        name: "batteryinfo_cycle_count",
+        help: "The number of charge cycles of the battery.",
+        value: |snapshot| snapshot.cycle_count.map(f64::from),
+    },
+    Gauge {
+        name: "batteryinfo_time_to_empty_seconds",
+        help: "The estimated time until the battery is empty in seconds.",
+        value: |snapshot| snapshot.time_to_empty_seconds,
+    },
+    Gauge {
+        name: "batteryinfo_time_to_full_seconds",
+        help: "The estimated time until the battery is full in seconds.",
+        value: |snapshot| snapshot.time_to_full_seconds,
+    },
+];
+
+/// Returns the value of a measurement in the given base units.
+fn base(measurement: &Measurement, units: &str) -> f64 {
+    convert(widen(measurement.value), &measurement.units, units)
+        .expect("snapshot units are convertible to base units")
+}
+
+/// Escapes a label value: backslashes, double quotes and line feeds.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Returns the labels of a battery, followed by the extra labels, without braces.
+fn labels(snapshot: &BatterySnapshot, extra: &[(&str, &str)]) -> String {
+    let optional = |value: &Option<String>| value.clone().unwrap_or_default();
+    let mut labels = vec![
+        ("battery_index", snapshot.index.to_string()),
+        ("vendor", optional(&snapshot.vendor)),
+        ("model", optional(&snapshot.model)),
+        ("serial_number", optional(&snapshot.serial_number)),
+    ];
+    labels.extend(extra.iter().map(|(name, value)| (*name, value.to_string())));
+    labels
+        .iter()
+        .map(|(name, value)| format!("{}=\"{}\"", name, escape(value)))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Appends the `HELP` and `TYPE` lines of a gauge.
+fn header(output: &mut String, name: &str, help: &str) {
+    output.push_str(&format!(
+        "# HELP {} {}\n# TYPE {} gauge\n",
+        name, help, name
+    ));
+}
+
+/// Renders batteries as metrics in the Prometheus text exposition format.
+///
+/// Measurements are converted to base units. Temperatures are only exact when the snapshots
+/// are taken in °C, since a conversion back from another unit is rounded.
+///
+/// Values that a battery does not report, such as the time to full while discharging, are left
+/// out. The state is exported as one `batteryinfo_state` series per state, with the value 1 for
+/// the current state and 0 for the others.
+pub fn render(snapshots: &[BatterySnapshot]) -> String {
+    let mut output = String::new();
+    for gauge in &GAUGES {
+        header(&mut output, gauge.name, gauge.help);
+        for snapshot in snapshots {
+            if let Some(value) = (gauge.value)(snapshot) {
+                output.push_str(&format!(
+                    "{}{{{}}} {}\n",
+                    gauge.name,
+                    labels(snapshot, &[]),
+                    value
+                ));
+            }
+        }
+    }
+    header(
+        &mut output,
+        "batteryinfo_state",
+        "Whether the battery is in the state given by the state label.",
+    );
+    for snapshot in snapshots {
+        for state in BatteryState::ALL {
+            output.push_str(&format!(
+                "batteryinfo_state{{{}}} {}\n",
+                labels(snapshot, &[("state", state.name())]),
+                u8::from(state == snapshot.state)
+            ));
+        }
+    }
+    output
+}
+
+/// Reads every battery and renders it as metrics.
+///
+/// # Arguments
+///
+/// * `sysfs_root` - Read the batteries from the Linux sysfs tree mounted at this path instead
+///   of the platform battery API (optional).
+pub fn collect(sysfs_root: Option<&Path>) -> Result<String> {
+    let backend = Backend::new(sysfs_root);
+    let display = DisplaySettings::new(TimeFormat::Seconds, TempUnit::DegC);
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64();
+    let snapshots = backend
+        .open_all()?
+        .into_iter()
+        .map(|mut source| {
+            let reading = source.read()?;
+            Ok(display.snapshot(source.index(), &reading, timestamp))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(render(&snapshots))
+}
+
+/// Reads every battery and writes its metrics to a file for the node_exporter textfile
+/// collector.
+///
+/// The metrics are written to a temporary file in the same directory, which is then renamed
+/// over `path`, so the collector never reads a partly written file. The temporary file does
+/// not end in `.prom`, so the collector ignores it.
+///
+/// # Arguments
+///
+/// * `path` - The file to write, e.g. `/var/lib/node_exporter/textfile/battery.prom`.
+/// * `sysfs_root` - Read the batteries from the Linux sysfs tree mounted at this path instead
+///   of the platform battery API (optional).
+pub fn write_textfile(path: &Path, sysfs_root: Option<&Path>) -> Result<()> {
+    let name = path.file_name().ok_or_else(|| {
+        Error::Io(
+            path.to_path_buf(),
+            io::Error::new(io::ErrorKind::InvalidInput, "not a file name"),
+        )
+    })?;
+    let metrics = collect(sysfs_root)?;
+    let mut temporary = name.to_os_string();
+    temporary.push(format!(".{}.tmp", process::id()));
+    let temporary = path.with_file_name(temporary);
+    fs::write(&temporary, metrics).map_err(|e| Error::Io(temporary.clone(), e))?;
+    fs::rename(&temporary, path).map_err(|e| {
+        let _ = fs::remove_file(&temporary);
+        Error::Io(path.to_path_buf(), e)
+    })
+}
+
+/// Serves the metrics of every battery over HTTP on a background thread.
+///
+/// The batteries are read on every request to `/metrics`. Each connection is answered on a
+/// thread of its own, up to 8 at once, and further connections are closed until one finishes.
+/// A client has 5 seconds to send its request and read the response, so a slow client holds a
+/// connection for at most that long.
+#[cfg_attr(feature = "python", pyclass(frozen))]
+#[derive(Debug)]
+pub struct MetricsServer {
+    /// The address the server listens on.
+    address: SocketAddr,
+    /// Tells the background thread to stop at the next connection.
+    stopping: Arc<AtomicBool>,
+    /// The background thread, while running.
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl MetricsServer {
+    /// Starts serving the metrics.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The address to listen on, e.g. `127.0.0.1:9101`. Port 0 picks a free port.
+    /// * `sysfs_root` - Read the batteries from the Linux sysfs tree mounted at this path
+    ///   instead of the platform battery API (optional).
+    pub fn start(address: &str, sysfs_root: Option<PathBuf>) -> Result<MetricsServer> {
+        let listener =
+            TcpListener::bind(address).map_err(|e| Error::Bind(address.to_string(), e))?;
+        let bound = listener
+            .local_addr()
+            .map_err(|e| Error::Bind(address.to_string(), e))?;
+        let stopping = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let stopping = stopping.clone();
+            thread::Builder::new()
+                .name("batteryinfo-metrics".to_string())
+                .spawn(move || serve(listener, sysfs_root, &stopping))
+                .map_err(|e| Error::Bind(address.to_string(), e))?
+        };
+        Ok(MetricsServer {
+            address: bound,
+            stopping,
+            thread: Mutex::new(Some(thread)),
+        })
+    }
+
+    /// Returns the address the server listens on, with the port that was picked for port 0.
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Returns whether the server is running.
+    pub fn is_running(&self) -> bool {
+        self.lock_thread().is_some()
+    }
+
+    /// Stops the server and waits for the background thread to finish.
+    pub fn shutdown(&self) {
+        let Some(thread) = self.lock_thread().take() else {
+            return;
+        };
+        self.stopping.store(true, Ordering::SeqCst);
+        // The thread is blocked accepting connections, so connect to wake it up.
+        let mut wake = self.address;
+        if wake.ip().is_unspecified() {
+            wake.set_ip(match wake {
+                SocketAddr::V4(_) => Ipv4Addr::LOCALHOST.into(),
+                SocketAddr::V6(_) => Ipv6Addr::LOCALHOST.into(),
+            });
+        }
+        let _ = TcpStream::connect_timeout(&wake, REQUEST_TIMEOUT);
+        let _ = thread.join();
+    }
+
+    /// Locks the background thread handle, ignoring poisoning.
+    fn lock_thread(&self) -> MutexGuard<'_, Option<JoinHandle<()>>> {
+        self.thread.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for MetricsServer {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl MetricsServer {
+    /// Returns the host the server listens on.
+    #[getter]
+    fn host(&self) -> String {
+        self.address.ip().to_string()
+    }
+
+    /// Returns the port the server listens on.
+    #[getter]
+    fn port(&self) -> u16 {
+        self.address.port()
+    }
+
+    /// Returns whether the server is running.
+    #[getter]
+    fn running(&self) -> bool {
+        self.is_running()
+    }
+
+    /// Stops the server. Stopping a server that is not running does nothing.
+    fn stop(&self, py: Python<'_>) {
+        py.allow_threads(|| self.shutdown());
+    }
+
+    /// Returns the server when used as a context manager.
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    /// Stops the server at the end of a `with` block.
+    fn __exit__(
+        &self,
+        py: Python<'_>,
+        _exc_type: PyObject,
+        _exc_value: PyObject,
+        _traceback: PyObject,
+    ) {
+        self.stop(py);
+    }
+
+    /// Returns a string representation of the server.
+    fn __repr__(&self) -> String {
+        format!(
+            "MetricsServer(address='{}', running={})",
+            self.address,
+            if self.is_running() { "True" } else { "False" }
+        )
+    }
+}
+
+/// Answers connections until the server is stopped, each on its own thread so a slow client
+/// does not hold up the others. At most `MAX_CONNECTIONS` are answered at once.
+fn serve(listener: TcpListener, sysfs_root: Option<PathBuf>, stopping: &AtomicBool) {
+    let active = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        if stopping.load(Ordering::SeqCst) {
+            break;
+        }
+        // A client that goes away must not stop the server.
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let Some(slot) = Slot::take(&active) else {
+            continue;
+        };
+        let sysfs_root = sysfs_root.clone();
+        let _ = thread::Builder::new()
+            .name("batteryinfo-metrics-request".to_string())
+            .spawn(move || {
+                let _slot = slot;
+                respond(stream, sysfs_root.as_deref())
+            });
+    }
+}
+
+/// One of the `MAX_CONNECTIONS` connections the server answers at once, given back when dropped.
+struct Slot(Arc<AtomicUsize>);
+
+impl Slot {
+    /// Takes a slot, or returns `None` if they are all in use.
+    fn take(active: &Arc<AtomicUsize>) -> Option<Slot> {
+        active
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| {
+                (count < MAX_CONNECTIONS).then_some(count + 1)
+            })
+            .ok()
+            .map(|_| Slot(active.clone()))
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// A connection that must be done with by a deadline, however slowly the client sends.
+struct Deadline<'a> {
+    /// The connection.
+    stream: &'a TcpStream,
+    /// When the client runs out of time.
+    until: Instant,
+}
+
+impl Deadline<'_> {
+    /// Limits the next read or write to the time left before the deadline.
+    fn limit(&self) -> io::Result<()> {
+        let left = self.until.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Err(io::ErrorKind::TimedOut.into());
+        }
+        self.stream.set_read_timeout(Some(left))?;
+        self.stream.set_write_timeout(Some(left))
+    }
+}
+
+impl Read for Deadline<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.limit()?;
+        self.stream.read(buf)
+    }
+}
+
+impl Write for Deadline<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.limit()?;
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+/// Reads a request and answers it: the metrics at `/metrics`, a link to them at `/`, and an
+/// error otherwise. The client gets `REQUEST_TIMEOUT` for the whole exchange.
+fn respond(stream: TcpStream, sysfs_root: Option<&Path>) -> io::Result<()> {
+    let mut stream = Deadline {
+        stream: &stream,
+        until: Instant::now() + REQUEST_TIMEOUT,
+    };
+    let request = read_request(&mut BufReader::new(&mut stream))?;
+    let mut parts = request.as_deref().unwrap_or_default().split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let path = parts.next().unwrap_or_default();
+    let path = path.split('?').next().unwrap_or_default();
+    let text = "text/plain; charset=utf-8";
+    let (status, content_type, body) = match (method, path) {
+        _ if request.is_none() => ("400 Bad Request", text, "Bad request\n".to_string()),
+        ("GET" | "HEAD", "/metrics") => match collect(sysfs_root) {
+            Ok(metrics) => ("200 OK", CONTENT_TYPE, metrics),
+            Err(e) => ("500 Internal Server Error", text, format!("{}\n", e)),
+        },
+        ("GET" | "HEAD", "/") => (
+            "200 OK",
+            "text/html; charset=utf-8",
+            "<html><body><a href=\"/metrics\">Metrics</a></body></html>\n".to_string(),
+        ),
+        ("GET" | "HEAD", _) => ("404 Not Found", text, "Not found\n".to_string()),
+        _ => (
+            "405 Method Not Allowed",
+            text,
+            "Method not allowed\n".to_string(),
+        ),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    )?;
+    if method != "HEAD" {
+        stream.write_all(body.as_bytes())?;
+    }
+    stream.flush()
+}
+
+/// Reads the request line and the headers of a request, and returns the request line, or
+/// `None` if a line is longer than `MAX_LINE` or there are more than `MAX_HEADERS` headers.
+fn read_request(reader: &mut impl BufRead) -> io::Result<Option<String>> {
+    let Some(request) = read_line(reader)? else {
+        return Ok(None);
+    };
+    // The headers are not needed, but are read so the client is not reset before it is done.
+    for _ in 0..=MAX_HEADERS {
+        match read_line(reader)? {
+            Some(header) if !header.trim_end().is_empty() => continue,
+            Some(_) => return Ok(Some(request)),
+            None => return Ok(None),
+        }
+    }
+    Ok(None)
+}
+
+/// Reads a line of at most `MAX_LINE` bytes, or returns `None` if it is longer. The end of the
+/// request counts as an empty line.
+fn read_line(reader: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    let read = reader.by_ref().take(MAX_LINE).read_line(&mut line)?;
+    Ok((read as u64 != MAX_LINE || line.ends_with('\n')).then_some(line))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sends a request to a server and returns the response.
+    fn send(server: &MetricsServer, request: &[u8]) -> String {
+        let mut stream = TcpStream::connect(server.address()).unwrap();
+        stream.write_all(request).unwrap();
+        let mut response = String::new();
+        let _ = stream.read_to_string(&mut response);
+        response
+    }
+
+    #[test]
+    fn answers_while_a_client_is_slow() {
+        let server = MetricsServer::start("127.0.0.1:0", None).unwrap();
+        let mut slow = TcpStream::connect(server.address()).unwrap();
+        slow.write_all(b"GET / HTTP/1.1\r\n").unwrap();
+        let started = Instant::now();
+        let response = send(&server, b"GET / HTTP/1.1\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+        assert!(started.elapsed() < REQUEST_TIMEOUT);
+    }
+
+    #[test]
+    fn closes_a_slow_connection_at_the_deadline() {
+        let server = MetricsServer::start("127.0.0.1:0", None).unwrap();
+        let mut slow = TcpStream::connect(server.address()).unwrap();
+        let started = Instant::now();
+        // Sends a byte a second, so no single read times out.
+        while slow.write_all(b"G").is_ok() && started.elapsed() < REQUEST_TIMEOUT * 2 {
+            let mut byte = [0];
+            slow.set_read_timeout(Some(Duration::from_secs(1))).unwrap();
+            if matches!(slow.read(&mut byte), Ok(0)) {
+                break;
+            }
+        }
+        assert!(started.elapsed() < REQUEST_TIMEOUT + Duration::from_secs(2));
+    }
+
+    #[test]
+    fn rejects_a_long_request_line() {
+        let server = MetricsServer::start("127.0.0.1:0", None).unwrap();
+        let mut request = b"GET /".to_vec();
+        request.resize(MAX_LINE as usize, b'a');
+        let response = send(&server, &request);
+        assert!(
+            response.starts_with("HTTP/1.1 400 Bad Request\r\n"),
+            "{}",
+            response
+        );
+    }
+
+    #[test]
+    fn rejects_too_many_headers() {
+        let server = MetricsServer::start("127.0.0.1:0", None).unwrap();
+        let request = format!(
+            "GET / HTTP/1.1\r\n{}\r\n",
+            "X: y\r\n".repeat(MAX_HEADERS + 1)
+        );
+        let response = send(&server, request.as_bytes());
+        assert!(
+            response.starts_with("HTTP/1.1 400 Bad Request\r\n"),
+            "{}",
+            response
+        );
+    }
+
+    #[test]
+    fn closes_connections_beyond_the_limit() {
+        let server = MetricsServer::start("127.0.0.1:0", None).unwrap();
+        let _busy: Vec<_> = (0..MAX_CONNECTIONS)
+            .map(|_| TcpStream::connect(server.address()).unwrap())
+            .collect();
+        assert_eq!(send(&server, b"GET / HTTP/1.1\r\n\r\n"), "");
+    }
+
+    #[test]
+    fn reads_the_request_line() {
+        let mut reader = io::Cursor::new(b"GET /metrics HTTP/1.1\r\nHost: x\r\n\r\n".to_vec());
+        assert_eq!(
+            read_request(&mut reader).unwrap().as_deref(),
+            Some("GET /metrics HTTP/1.1\r\n")
+        );
+    }
+}
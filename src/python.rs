@@ -0,0 +1,298 @@
+//! The `batteryinfo` Python module.
+
+use pyo3::prelude::*;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::asyncio::BatteryWatch;
+use crate::battery::Battery;
+use crate::enums::{BatteryState, BatteryTechnology, StatusBar, TempUnit, TimeFormat};
+use crate::measurement::Measurement;
+use crate::monitor::BatteryMonitor;
+use crate::prometheus::{self, MetricsServer};
+use crate::snapshot::BatterySnapshot;
+use crate::source::Backend;
+use crate::{json, locale, testing};
+
+/// Returns every battery reported by the system.
+///
+/// # Arguments
+///
+/// * `time_format` - The format for displaying time (default: `TimeFormat::Human`).
+/// * `temp_unit` - The unit for displaying temperature (default: `TempUnit::DegF`).
+/// * `refresh_interval` - The interval for refreshing the battery information (default: 500 ms).
+/// * `sysfs_root` - Read the batteries from the Linux sysfs tree mounted at this path instead of
+///   the platform battery API (optional).
+/// * `precision` - The number of decimals to display per field, e.g. `{"voltage": 3}`
+///   (default: 1 for every field).
+/// * `locale` - The locale for displaying times and numbers, e.g. `de` (default: the global
+///   locale).
+///
+/// # Returns
+///
+/// A list with one `Battery` instance per device, ordered by index. The list is empty if the
+/// system has no batteries.
+#[pyfunction]
+#[pyo3(signature = (
+    time_format=TimeFormat::Human,
+    temp_unit=TempUnit::DegF,
+    refresh_interval=500,
+    sysfs_root=None,
+    precision=None,
+    locale=None,
+))]
+fn batteries(
+    py: Python<'_>,
+    time_format: TimeFormat,
+    temp_unit: TempUnit,
+    refresh_interval: u64,
+    sysfs_root: Option<PathBuf>,
+    precision: Option<HashMap<String, usize>>,
+    locale: Option<String>,
+) -> PyResult<Vec<Battery>> {
+    Battery::all(
+        py,
+        &Backend::new(sysfs_root.as_deref()),
+        time_format,
+        temp_unit,
+        Duration::from_millis(refresh_interval),
+    )?
+    .into_iter()
+    .map(|battery| {
+        battery
+            .with_precision(precision.clone())?
+            .with_locale(locale.clone())
+    })
+    .collect()
+}
+
+/// Returns the number of batteries reported by the system.
+///
+/// # Arguments
+///
+/// * `sysfs_root` - Count the batteries in the Linux sysfs tree mounted at this path instead of
+///   the platform battery API (optional).
+#[pyfunction]
+#[pyo3(signature = (sysfs_root=None))]
+fn battery_count(py: Python<'_>, sysfs_root: Option<PathBuf>) -> PyResult<usize> {
+    Ok(py.allow_threads(|| Backend::new(sysfs_root.as_deref()).count())?)
+}
+
+/// Returns the JSON Schema document that `Battery.to_json` and `BatterySnapshot.to_json`
+/// payloads validate against.
+#[pyfunction]
+fn json_schema() -> &'static str {
+    json::SCHEMA
+}
+
+/// Returns the metrics of every battery in the Prometheus text exposition format.
+///
+/// The batteries are read on every call. Every series is labelled with `battery_index`,
+/// `vendor`, `model` and `serial_number`.
+///
+/// # Arguments
+///
+/// * `sysfs_root` - Read the batteries from the Linux sysfs tree mounted at this path instead of
+///   the platform battery API (optional).
+#[pyfunction]
+#[pyo3(signature = (sysfs_root=None))]
+fn prometheus_metrics(py: Python<'_>, sysfs_root: Option<PathBuf>) -> PyResult<String> {
+    Ok(py.allow_threads(|| prometheus::collect(sysfs_root.as_deref()))?)
+}
+
+/// Writes the metrics of every battery to a file for the node_exporter textfile collector.
+///
+/// The file is replaced in one step, so the collector never reads a partly written file.
+///
+/// # Arguments
+///
+/// * `path` - The file to write, e.g. `/var/lib/node_exporter/textfile/battery.prom`.
+/// * `sysfs_root` - Read the batteries from the Linux sysfs tree mounted at this path instead of
+///   the platform battery API (optional).
+#[pyfunction]
+#[pyo3(signature = (path, sysfs_root=None))]
+fn write_prometheus_textfile(
+    py: Python<'_>,
+    path: PathBuf,
+    sysfs_root: Option<PathBuf>,
+) -> PyResult<()> {
+    Ok(py.allow_threads(|| prometheus::write_textfile(&path, sysfs_root.as_deref()))?)
+}
+
+/// Starts serving the metrics of every battery over HTTP at `/metrics`, on a background thread.
+///
+/// # Arguments
+///
+/// * `port` - The port to listen on. Port 0 picks a free port.
+/// * `host` - The address to listen on (default: `127.0.0.1`, this machine only).
+/// * `sysfs_root` - Read the batteries from the Linux sysfs tree mounted at this path instead of
+///   the platform battery API (optional).
+///
+/// # Returns
+///
+/// The running `MetricsServer`, which serves until it is stopped or the process exits.
+#[pyfunction]
+#[pyo3(signature = (port, host="127.0.0.1", sysfs_root=None))]
+fn start_metrics_server(
+    py: Python<'_>,
+    port: u16,
+    host: &str,
+    sysfs_root: Option<PathBuf>,
+) -> PyResult<MetricsServer> {
+    let address = if host.contains(':') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    };
+    Ok(py.allow_threads(|| MetricsServer::start(&address, sysfs_root))?)
+}
+
+/// Sets the global locale, used by every `Battery` that does not have a locale of its own.
+///
+/// # Arguments
+///
+/// * `locale` - The code of a registered locale, e.g. `de`. A code with a region, e.g.
+///   `de-AT`, falls back to its language.
+#[pyfunction]
+fn set_locale(locale: &str) -> PyResult<()> {
+    Ok(locale::set_global(locale)?)
+}
+
+/// Returns the code of the global locale.
+#[pyfunction]
+fn get_locale() -> String {
+    locale::global()
+}
+
+/// Returns the codes of the registered locales.
+#[pyfunction]
+fn available_locales() -> Vec<String> {
+    locale::codes()
+}
+
+/// Registers a locale, or replaces a registered one.
+///
+/// The locale starts as a copy of `base`, and only the given values are changed. Words are
+/// templates in which `{}` is replaced by the number, e.g. `{} hours`.
+///
+/// # Arguments
+///
+/// * `code` - The code of the locale, e.g. `pt` or `pt-BR`.
+/// * `base` - The registered locale to start from (default: `en`).
+/// * `decimal_separator` - The decimal separator, e.g. `,` (optional).
+/// * `units` - The singular and plural words per unit of time, e.g.
+///   `{"hour": ("{} hora", "{} horas")}`. The units are `day`, `hour`, `minute` and `second`
+///   (optional).
+/// * `short_units` - The abbreviated units of time used by `TimeFormat.Human`, e.g.
+///   `{"hour": "{} h"}` (optional).
+/// * `separator` - The separator between the parts of a `TimeFormat.Human` time (optional).
+/// * `about` - The template for `TimeFormat.Approximate` times, e.g. `cerca de {}`
+///   (optional).
+/// * `less_than_a_minute` - The `TimeFormat.Approximate` text under a minute (optional).
+#[pyfunction]
+#[pyo3(signature = (
+    code,
+    base="en",
+    decimal_separator=None,
+    units=None,
+    short_units=None,
+    separator=None,
+    about=None,
+    less_than_a_minute=None,
+))]
+#[allow(clippy::too_many_arguments)]
+fn register_locale(
+    code: &str,
+    base: &str,
+    decimal_separator: Option<char>,
+    units: Option<HashMap<String, (String, String)>>,
+    short_units: Option<HashMap<String, String>>,
+    separator: Option<String>,
+    about: Option<String>,
+    less_than_a_minute: Option<String>,
+) -> PyResult<()> {
+    let mut new_locale = (*locale::find(base)?).clone();
+    if let Some(decimal_separator) = decimal_separator {
+        new_locale.decimal_separator = decimal_separator;
+    }
+    if let Some(units) = units {
+        new_locale.set_units(units)?;
+    }
+    if let Some(short_units) = short_units {
+        new_locale.set_short_units(short_units)?;
+    }
+    if let Some(separator) = separator {
+        new_locale.separator = separator;
+    }
+    if let Some(about) = about {
+        new_locale.set_about(about)?;
+    }
+    if let Some(less_than_a_minute) = less_than_a_minute {
+        new_locale.less_than_a_minute = less_than_a_minute;
+    }
+    locale::register(code, new_locale);
+    Ok(())
+}
+
+/// The `batteryinfo` module provides classes and functions to interact with system batteries.
+///
+/// This module includes the following classes:
+/// - `Battery`: Represents a system battery with properties like charge, voltage, and temperature.
+/// - `BatteryMonitor`: Watches a battery on a background thread and calls callbacks when it changes.
+/// - `BatteryState`: Enum representing the state of a battery (charging, discharging, etc.).
+/// - `BatteryTechnology`: Enum representing the chemistry of a battery, with typical figures for it.
+/// - `BatterySnapshot`: An immutable copy of the battery information taken from a single refresh.
+/// - `BatteryWatch`: An asynchronous iterator of snapshots, returned by `Battery.watch()`.
+/// - `Measurement`: Represents a measurement with a value, units, and precision.
+/// - `MetricsServer`: Serves battery metrics to Prometheus over HTTP, from `start_metrics_server()`.
+/// - `TimeFormat`: Enum representing the format for displaying time.
+/// - `TempUnit`: Enum representing the unit for displaying temperature.
+/// - `StatusBar`: Enum representing a status bar that battery information can be rendered for.
+///
+/// And the following functions:
+/// - `batteries`: Returns every battery reported by the system.
+/// - `battery_count`: Returns the number of batteries reported by the system.
+/// - `json_schema`: Returns the JSON Schema document of the JSON payloads.
+/// - `set_locale`, `get_locale`, `available_locales` and `register_locale`: Choose and add
+///   locales for displaying times and numbers.
+/// - `prometheus_metrics`, `write_prometheus_textfile` and `start_metrics_server`: Export the
+///   batteries as Prometheus metrics.
+///
+/// And the `batteryinfo.testing` submodule, with helpers for testing applications on machines
+/// without a battery.
+#[pymodule]
+fn batteryinfo(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Battery>()?;
+    m.add_class::<BatteryMonitor>()?;
+    m.add_class::<BatteryState>()?;
+    m.add_class::<BatteryTechnology>()?;
+    m.add_class::<BatterySnapshot>()?;
+    m.add_class::<BatteryWatch>()?;
+    m.add_class::<Measurement>()?;
+    m.add_class::<MetricsServer>()?;
+    m.add_class::<TimeFormat>()?;
+    m.add_class::<TempUnit>()?;
+    m.add_class::<StatusBar>()?;
+    m.add("JSON_SCHEMA_VERSION", json::SCHEMA_VERSION)?;
+    m.add_function(wrap_pyfunction!(batteries, m)?)?;
+    m.add_function(wrap_pyfunction!(battery_count, m)?)?;
+    m.add_function(wrap_pyfunction!(json_schema, m)?)?;
+    m.add_function(wrap_pyfunction!(set_locale, m)?)?;
+    m.add_function(wrap_pyfunction!(get_locale, m)?)?;
+    m.add_function(wrap_pyfunction!(available_locales, m)?)?;
+    m.add_function(wrap_pyfunction!(register_locale, m)?)?;
+    m.add_function(wrap_pyfunction!(prometheus_metrics, m)?)?;
+    m.add_function(wrap_pyfunction!(write_prometheus_textfile, m)?)?;
+    m.add_function(wrap_pyfunction!(start_metrics_server, m)?)?;
+
+    let testing_module = PyModule::new(m.py(), "testing")?;
+    testing::register(&testing_module)?;
+    m.add_submodule(&testing_module)?;
+    // Make `import batteryinfo.testing` work, not just attribute access.
+    m.py()
+        .import("sys")?
+        .getattr("modules")?
+        .set_item("batteryinfo.testing", &testing_module)?;
+    Ok(())
+}
@@ -0,0 +1,151 @@
+//! Recording battery readings to JSON Lines files.
+//!
+//! Each line of a recording is a JSON object holding one reading: the battery index, the time
+//! it was read, and every value in the units used by `Reading` (Wh, W, V, °C and seconds).
+
+use serde_json::{Value, json};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::error::{Error, Result};
+use crate::source::{Reading, parse_state, parse_technology};
+
+/// A reading together with when and from which battery it was taken.
+#[derive(Debug, Clone)]
+pub struct Record {
+    /// The time the reading was taken, in seconds since the Unix epoch.
+    pub timestamp: f64,
+    /// The index of the battery.
+    pub index: usize,
+    /// The values read from the battery.
+    pub reading: Reading,
+}
+
+impl Record {
+    /// Encodes the record as a JSON object.
+    pub fn to_json(&self) -> Value {
+        let reading = &self.reading;
+        json!({
+            "timestamp": self.timestamp,
+            "index": self.index,
+            "vendor": reading.vendor,
+            "model": reading.model,
+            "serial_number": reading.serial_number,
+            "technology": reading.technology.name(),
+            "state": reading.state.name(),
+            "percent": reading.state_of_charge,
+            "capacity": reading.state_of_health,
+            "temperature": reading.temperature,
+            "cycle_count": reading.cycle_count,
+            "energy": reading.energy,
+            "energy_full": reading.energy_full,
+            "energy_full_design": reading.energy_full_design,
+            "energy_rate": reading.energy_rate,
+            "voltage": reading.voltage,
+            "time_to_empty": reading.time_to_empty,
+            "time_to_full": reading.time_to_full,
+        })
+    }
+
+    /// Decodes a record from a JSON object written by `to_json`.
+    ///
+    /// Returns a description of the problem if a required value is missing or invalid.
+    pub fn from_json(value: &Value) -> std::result::Result<Self, String> {
+        let number = |key: &str| {
+            value[key]
+                .as_f64()
+                .ok_or_else(|| format!("missing or invalid \"{}\"", key))
+        };
+        let optional_number = |key: &str| value[key].as_f64().map(|n| n as f32);
+        let string = |key: &str| value[key].as_str().map(str::to_string);
+
+        Ok(Record {
+            timestamp: number("timestamp")?,
+            index: number("index")? as usize,
+            reading: Reading {
+                vendor: string("vendor"),
+                model: string("model"),
+                serial_number: string("serial_number"),
+                technology: parse_technology(value["technology"].as_str().unwrap_or_default()),
+                state: parse_state(value["state"].as_str().unwrap_or_default()),
+                state_of_charge: number("percent")? as f32,
+                state_of_health: number("capacity")? as f32,
+                temperature: optional_number("temperature"),
+                cycle_count: value["cycle_count"].as_u64().map(|c| c as u32),
+                energy: number("energy")? as f32,
+                energy_full: number("energy_full")? as f32,
+                energy_full_design: number("energy_full_design")? as f32,
+                energy_rate: number("energy_rate")? as f32,
+                voltage: number("voltage")? as f32,
+                time_to_empty: optional_number("time_to_empty"),
+                time_to_full: optional_number("time_to_full"),
+            },
+        })
+    }
+}
+
+/// Reads every record from a JSON Lines file. Blank lines are skipped.
+pub fn read_records(path: &Path) -> Result<Vec<Record>> {
+    let contents = std::fs::read_to_string(path).map_err(|e| Error::Io(path.to_path_buf(), e))?;
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(number, line)| {
+            let invalid = |message: String| {
+                Error::InvalidData(format!(
+                    "{}, line {}: {}",
+                    path.display(),
+                    number + 1,
+                    message
+                ))
+            };
+            let value: Value = serde_json::from_str(line).map_err(|e| invalid(e.to_string()))?;
+            Record::from_json(&value).map_err(invalid)
+        })
+        .collect()
+}
+
+/// Writes records to a JSON Lines file, one line per record.
+#[derive(Debug)]
+pub struct Recorder {
+    /// The path of the file being written.
+    path: PathBuf,
+    /// The writer for the file.
+    writer: BufWriter<File>,
+}
+
+impl Recorder {
+    /// Creates a recorder that writes to the given file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The file to write to.
+    /// * `append` - Whether to append to an existing file instead of replacing it.
+    pub fn create(path: &Path, append: bool) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(path)
+            .map_err(|e| Error::Io(path.to_path_buf(), e))?;
+        Ok(Recorder {
+            path: path.to_path_buf(),
+            writer: BufWriter::new(file),
+        })
+    }
+
+    /// Returns the path of the file being written.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Writes a record and flushes it, so the file is complete even if the process exits.
+    pub fn write(&mut self, record: &Record) -> Result<()> {
+        writeln!(self.writer, "{}", record.to_json())
+            .and_then(|_| self.writer.flush())
+            .map_err(|e| Error::Io(self.path.clone(), e))
+    }
+}
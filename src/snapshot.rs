@@ -0,0 +1,190 @@
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3::types::PyDict;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+#[cfg(feature = "python")]
+use std::collections::HashMap;
+
+#[cfg(feature = "python")]
+use crate::dict::{self, DictOptions};
+use crate::duration::FormattedTime;
+#[cfg(feature = "python")]
+use crate::enums::StatusBar;
+use crate::enums::{BatteryState, BatteryTechnology};
+#[cfg(feature = "python")]
+use crate::json;
+use crate::json::SCHEMA_VERSION;
+use crate::measurement::Measurement;
+#[cfg(feature = "python")]
+use crate::status::{self, StatusStyle};
+
+/// An immutable copy of the battery information taken from a single refresh.
+///
+/// Unlike the getters on `Battery`, which may refresh between two reads, every field of a
+/// snapshot belongs to the same sample.
+#[cfg_attr(feature = "python", pyclass(frozen, get_all))]
+#[derive(Debug, Clone)]
+pub struct BatterySnapshot {
+    /// The index of the battery.
+    pub index: usize,
+    /// The vendor of the battery.
+    pub vendor: Option<String>,
+    /// The model of the battery.
+    pub model: Option<String>,
+    /// The serial number of the battery.
+    pub serial_number: Option<String>,
+    /// The technology of the battery.
+    pub technology: BatteryTechnology,
+    /// The percentage of the battery that is full.
+    pub percent: Measurement,
+    /// The state of the battery (charging, discharging, etc.).
+    pub state: BatteryState,
+    /// The capacity of the battery.
+    pub capacity: Measurement,
+    /// The temperature of the battery.
+    pub temperature: Option<Measurement>,
+    /// The cycle count of the battery.
+    pub cycle_count: Option<u32>,
+    /// The current energy of the battery.
+    pub energy: Measurement,
+    /// The full energy of the battery.
+    pub energy_full: Measurement,
+    /// The design energy of the battery.
+    pub energy_full_design: Measurement,
+    /// The energy rate of the battery.
+    pub energy_rate: Measurement,
+    /// The voltage of the battery.
+    pub voltage: Measurement,
+    /// The time to empty the battery, in the time format of the battery.
+    pub time_to_empty: Option<FormattedTime>,
+    /// The time to fully charge the battery, in the time format of the battery.
+    pub time_to_full: Option<FormattedTime>,
+    /// The time to empty the battery in seconds.
+    pub time_to_empty_seconds: Option<f64>,
+    /// The time to fully charge the battery in seconds.
+    pub time_to_full_seconds: Option<f64>,
+    /// The time the values were read, in seconds since the Unix epoch.
+    pub timestamp: f64,
+}
+
+/// Serializes as a payload of the JSON schema in `schema/battery.schema.json`.
+///
+/// Times are given in seconds, whatever the time format of the battery is.
+impl Serialize for BatterySnapshot {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut fields = serializer.serialize_struct("BatterySnapshot", 19)?;
+        fields.serialize_field("schema_version", &SCHEMA_VERSION)?;
+        fields.serialize_field("battery_index", &self.index)?;
+        fields.serialize_field("timestamp", &self.timestamp)?;
+        fields.serialize_field("vendor", &self.vendor)?;
+        fields.serialize_field("model", &self.model)?;
+        fields.serialize_field("serial_number", &self.serial_number)?;
+        fields.serialize_field("technology", &self.technology)?;
+        fields.serialize_field("state", &self.state)?;
+        fields.serialize_field("percent", &self.percent)?;
+        fields.serialize_field("capacity", &self.capacity)?;
+        fields.serialize_field("temperature", &self.temperature)?;
+        fields.serialize_field("cycle_count", &self.cycle_count)?;
+        fields.serialize_field("energy", &self.energy)?;
+        fields.serialize_field("energy_full", &self.energy_full)?;
+        fields.serialize_field("energy_full_design", &self.energy_full_design)?;
+        fields.serialize_field("energy_rate", &self.energy_rate)?;
+        fields.serialize_field("voltage", &self.voltage)?;
+        fields.serialize_field("time_to_empty_seconds", &self.time_to_empty_seconds)?;
+        fields.serialize_field("time_to_full_seconds", &self.time_to_full_seconds)?;
+        fields.end()
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl BatterySnapshot {
+    /// Returns all snapshot information as a Python dictionary.
+    ///
+    /// # Arguments
+    ///
+    /// * `metadata` - Also include the units of each measurement under `"units"` (default:
+    ///   `False`). The `"timestamp"` is always included.
+    /// * `nested` - Represent measurements as `{"value": ..., "units": ...}` instead of tuples
+    ///   of `(value, units)` (default: `False`).
+    /// * `drop_none` - Leave out the fields whose value is `None` (default: `False`).
+    #[pyo3(signature = (*, metadata=false, nested=false, drop_none=false))]
+    fn as_dict<'py>(
+        &self,
+        py: Python<'py>,
+        metadata: bool,
+        nested: bool,
+        drop_none: bool,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let dict = dict::to_dict(py, self, DictOptions { nested, drop_none })?;
+        dict.set_item("timestamp", self.timestamp)?;
+        if metadata {
+            dict.set_item("units", dict::units(py, self)?)?;
+        }
+        Ok(dict)
+    }
+
+    /// Returns the snapshot as JSON.
+    ///
+    /// The JSON follows a versioned schema, available from `batteryinfo.json_schema()`.
+    /// Measurements are objects with a `value` and `units`, and times are in seconds.
+    ///
+    /// # Arguments
+    ///
+    /// * `pretty` - Whether to indent the JSON over several lines (default: `False`).
+    #[pyo3(signature = (pretty=false))]
+    pub fn to_json(&self, pretty: bool) -> PyResult<String> {
+        Ok(json::to_string(self, pretty)?)
+    }
+
+    /// Renders the snapshot for a status bar.
+    ///
+    /// The text shows an icon for the state, the percentage, and the time to empty or to full.
+    /// While the battery is not on external power, it is shown at the low or critical level
+    /// when the percentage is at or below the thresholds.
+    ///
+    /// # Arguments
+    ///
+    /// * `bar` - The status bar to render for, e.g. `StatusBar.Waybar`.
+    /// * `low` - The percentage at or below which the battery is low (default: 25).
+    /// * `critical` - The percentage at or below which the battery is critical (default: 10).
+    /// * `icons` - Icons to replace, by state (`charging`, `discharging`, `full`,
+    ///   `not_charging`, `empty`, `unknown`) or level (`low`, `critical`), e.g.
+    ///   `{"charging": "⚡"}` (optional).
+    /// * `classes` - Waybar CSS classes to replace, by level (default: `warning` and
+    ///   `critical`).
+    /// * `colors` - Colors to replace, by level (default: `#FFB52A` and `#FF5555`).
+    #[pyo3(signature = (bar, *, low=25.0, critical=10.0, icons=None, classes=None, colors=None))]
+    pub fn status(
+        &self,
+        bar: StatusBar,
+        low: f32,
+        critical: f32,
+        icons: Option<HashMap<String, String>>,
+        classes: Option<HashMap<String, String>>,
+        colors: Option<HashMap<String, String>>,
+    ) -> PyResult<String> {
+        let style = StatusStyle::new(
+            low,
+            critical,
+            icons.unwrap_or_default(),
+            classes.unwrap_or_default(),
+            colors.unwrap_or_default(),
+        )?;
+        Ok(status::render(bar, self, &style))
+    }
+
+    /// Returns a string representation of the snapshot.
+    fn __repr__(&self) -> PyResult<String> {
+        Ok(format!(
+            "BatterySnapshot(index={}, percent={}, state={}, energy={}, energy_rate={}, timestamp={})",
+            self.index,
+            self.percent,
+            self.state.name(),
+            self.energy,
+            self.energy_rate,
+            self.timestamp
+        ))
+    }
+}
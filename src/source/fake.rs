@@ -0,0 +1,137 @@
+use std::sync::{Arc, Mutex};
+
+use super::{Reading, Source, time_estimates};
+use crate::enums::{BatteryState, BatteryTechnology};
+use crate::error::{Error, Result};
+
+/// The scripted values of a virtual battery.
+///
+/// The percentage, capacity and time estimates are derived from the energy values whenever the
+/// battery is read, so they stay consistent as the values change.
+#[derive(Debug, Clone)]
+pub struct FakeState {
+    /// The vendor of the battery.
+    pub vendor: Option<String>,
+    /// The model of the battery.
+    pub model: Option<String>,
+    /// The serial number of the battery.
+    pub serial_number: Option<String>,
+    /// The technology of the battery.
+    pub technology: BatteryTechnology,
+    /// The state of the battery (charging, discharging, etc.).
+    pub state: BatteryState,
+    /// The temperature of the battery in degrees Celsius.
+    pub temperature: Option<f32>,
+    /// The cycle count of the battery.
+    pub cycle_count: Option<u32>,
+    /// The current energy of the battery in watt-hours.
+    pub energy: f32,
+    /// The full energy of the battery in watt-hours.
+    pub energy_full: f32,
+    /// The design energy of the battery in watt-hours.
+    pub energy_full_design: f32,
+    /// The energy rate of the battery in watts.
+    pub energy_rate: f32,
+    /// The voltage of the battery in volts.
+    pub voltage: f32,
+    /// Whether the battery is present. Reading a battery that is not present fails.
+    pub present: bool,
+}
+
+impl FakeState {
+    /// Returns the values a source would report for this state.
+    pub fn reading(&self) -> Reading {
+        let percent_of = |part: f32, whole: f32| {
+            if whole > 0.0 {
+                part / whole * 100.0
+            } else {
+                0.0
+            }
+        };
+        let (time_to_empty, time_to_full) =
+            time_estimates(self.state, self.energy, self.energy_full, self.energy_rate);
+        Reading {
+            vendor: self.vendor.clone(),
+            model: self.model.clone(),
+            serial_number: self.serial_number.clone(),
+            technology: self.technology,
+            state: self.state,
+            state_of_charge: percent_of(self.energy, self.energy_full).min(100.0),
+            state_of_health: percent_of(self.energy_full, self.energy_full_design),
+            temperature: self.temperature,
+            cycle_count: self.cycle_count,
+            energy: self.energy,
+            energy_full: self.energy_full,
+            energy_full_design: self.energy_full_design,
+            energy_rate: self.energy_rate,
+            voltage: self.voltage,
+            time_to_empty,
+            time_to_full,
+        }
+    }
+
+    /// Advances the battery by the given number of seconds at its current energy rate.
+    ///
+    /// A discharging battery loses energy and becomes `Empty` when it runs out. A charging
+    /// battery gains energy and becomes `Full` when it reaches its full energy. The energy of a
+    /// battery in any other state does not change.
+    pub fn step(&mut self, seconds: f32) {
+        let delta = self.energy_rate * seconds / 3600.0;
+        match self.state {
+            BatteryState::Discharging => {
+                self.energy = (self.energy - delta).max(0.0);
+                if self.energy == 0.0 {
+                    self.state = BatteryState::Empty;
+                }
+            }
+            BatteryState::Charging => {
+                self.energy = (self.energy + delta).min(self.energy_full);
+                if self.energy == self.energy_full {
+                    self.state = BatteryState::Full;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Reads a virtual battery whose values are scripted by a test.
+///
+/// The state is shared, so changes made through the `FakeBattery` that created the source are
+/// seen on the next read.
+#[derive(Debug)]
+pub struct FakeSource {
+    /// The shared state of the virtual battery.
+    state: Arc<Mutex<FakeState>>,
+    /// The index reported for the battery.
+    index: usize,
+}
+
+impl FakeSource {
+    /// Creates a source that reads the given shared state.
+    pub fn new(state: Arc<Mutex<FakeState>>, index: usize) -> Self {
+        FakeSource { state, index }
+    }
+}
+
+impl Source for FakeSource {
+    fn index(&self) -> usize {
+        self.index
+    }
+
+    fn read(&mut self) -> Result<Reading> {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if !state.present {
+            return Err(Error::NoBatteries);
+        }
+        Ok(state.reading())
+    }
+
+    /// A virtual battery only exists at its own index.
+    fn reopen(&self, index: usize) -> Result<Box<dyn Source>> {
+        if index != self.index {
+            return Err(Error::IndexOutOfRange);
+        }
+        Ok(Box::new(FakeSource::new(self.state.clone(), index)))
+    }
+}
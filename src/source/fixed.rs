@@ -0,0 +1,36 @@
+use super::{Reading, Source};
+use crate::error::{Error, Result};
+
+/// Reads a battery whose values never change, e.g. one rebuilt with `Battery.from_dict`.
+#[derive(Debug, Clone)]
+pub struct FixedSource {
+    /// The values returned by every read.
+    reading: Reading,
+    /// The index reported for the battery.
+    index: usize,
+}
+
+impl FixedSource {
+    /// Creates a source that always returns the given values.
+    pub fn new(reading: Reading, index: usize) -> Self {
+        FixedSource { reading, index }
+    }
+}
+
+impl Source for FixedSource {
+    fn index(&self) -> usize {
+        self.index
+    }
+
+    fn read(&mut self) -> Result<Reading> {
+        Ok(self.reading.clone())
+    }
+
+    /// A fixed battery only exists at its own index.
+    fn reopen(&self, index: usize) -> Result<Box<dyn Source>> {
+        if index != self.index {
+            return Err(Error::IndexOutOfRange);
+        }
+        Ok(Box::new(self.clone()))
+    }
+}
@@ -0,0 +1,191 @@
+use battery::Manager;
+use battery::units::{
+    electric_potential::volt, energy::watt_hour, power::watt, ratio::percent,
+    thermodynamic_temperature::degree_celsius,
+};
+use std::sync::{Arc, Mutex};
+
+use super::{Reading, Source};
+use crate::error::{Error, Result};
+
+/// Reads a battery through the platform battery API.
+///
+/// The manager and the battery handle are kept alive so the battery can be refreshed in place
+/// through `Manager::refresh`, which only re-reads the one device instead of enumerating every
+/// battery on the system. The sources opened together share one manager.
+#[derive(Debug)]
+pub struct ManagerSource {
+    /// The manager that reported the battery, shared with the other batteries it reported.
+    manager: Arc<Mutex<SharedManager>>,
+    /// The handle to the underlying battery.
+    battery: battery::Battery,
+    /// The index of the battery in the manager's list.
+    index: usize,
+}
+
+/// A manager shared by the sources it opened.
+#[derive(Debug)]
+struct SharedManager(Manager);
+
+// SAFETY: `battery::Manager` holds an `Rc` to the platform manager, which makes it `!Send`.
+// The only clones of that `Rc` live in the `Batteries` iterator, which is created and dropped
+// while the mutex around the manager is held, so the reference count is never touched from two
+// threads at once.
+unsafe impl Send for SharedManager {}
+
+// SAFETY: the battery handle holds no reference to the manager, and the manager is only used
+// through its mutex.
+unsafe impl Send for ManagerSource {}
+
+impl ManagerSource {
+    /// Opens the battery at the given index with a dedicated manager.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the battery to open.
+    ///
+    /// # Returns
+    ///
+    /// A `ManagerSource` for the battery, or an error if the system has no battery at that
+    /// index.
+    pub fn open(index: usize) -> Result<Self> {
+        let manager = Arc::new(Mutex::new(SharedManager(ManagerSource::manager()?)));
+        let batteries = ManagerSource::enumerate(&manager)?;
+        if batteries.is_empty() {
+            return Err(Error::NoBatteries);
+        }
+        let battery = batteries
+            .into_iter()
+            .nth(index)
+            .ok_or(Error::IndexOutOfRange)??;
+        Ok(ManagerSource {
+            manager,
+            battery,
+            index,
+        })
+    }
+
+    /// Opens every battery, enumerating them once with a manager they share.
+    ///
+    /// # Returns
+    ///
+    /// One `ManagerSource` per battery, in index order.
+    pub fn all() -> Result<Vec<Self>> {
+        let manager = Arc::new(Mutex::new(SharedManager(ManagerSource::manager()?)));
+        ManagerSource::enumerate(&manager)?
+            .into_iter()
+            .enumerate()
+            .map(|(index, battery)| {
+                Ok(ManagerSource {
+                    manager: manager.clone(),
+                    battery: battery?,
+                    index,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the number of batteries reported by the system.
+    pub fn count() -> Result<usize> {
+        let batteries = ManagerSource::manager()?
+            .batteries()
+            .map_err(|e| Error::Platform(format!("Failed to get batteries: {}", e)))?;
+        Ok(batteries.count())
+    }
+
+    /// Creates a new battery manager.
+    fn manager() -> Result<Manager> {
+        Manager::new().map_err(|e| Error::Platform(format!("Failed to create manager: {}", e)))
+    }
+
+    /// Lists the batteries reported by a manager, in index order.
+    fn enumerate(manager: &Mutex<SharedManager>) -> Result<Vec<Result<battery::Battery>>> {
+        let manager = manager.lock().unwrap_or_else(|e| e.into_inner());
+        let batteries = manager
+            .0
+            .batteries()
+            .map_err(|e| Error::Platform(format!("Failed to get batteries: {}", e)))?;
+        Ok(batteries
+            .map(|battery| {
+                battery.map_err(|e| Error::Platform(format!("Failed to get battery: {}", e)))
+            })
+            .collect())
+    }
+
+    /// Enumerates the batteries again and opens the one this source refers to.
+    ///
+    /// The battery with the same serial number is preferred, falling back to the same index.
+    fn reconnect(&self) -> Result<Self> {
+        let mut batteries = ManagerSource::enumerate(&self.manager)?;
+        if batteries.is_empty() {
+            return Err(Error::NoBatteries);
+        }
+        let position = self
+            .battery
+            .serial_number()
+            .and_then(|serial_number| {
+                batteries.iter().position(|battery| {
+                    battery
+                        .as_ref()
+                        .is_ok_and(|battery| battery.serial_number() == Some(serial_number))
+                })
+            })
+            .unwrap_or(self.index);
+        if position >= batteries.len() {
+            return Err(Error::IndexOutOfRange);
+        }
+        Ok(ManagerSource {
+            manager: self.manager.clone(),
+            battery: batteries.swap_remove(position)?,
+            index: self.index,
+        })
+    }
+}
+
+impl Source for ManagerSource {
+    fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Refreshes the battery in place.
+    ///
+    /// If the in-place refresh fails, for example because the battery was removed and the
+    /// handle is stale, the batteries are enumerated again and the battery is reopened.
+    fn read(&mut self) -> Result<Reading> {
+        let refreshed = {
+            let manager = self.manager.lock().unwrap_or_else(|e| e.into_inner());
+            manager.0.refresh(&mut self.battery)
+        };
+        if refreshed.is_err() {
+            *self = self.reconnect()?;
+        }
+        Ok(Reading::from(&self.battery))
+    }
+
+    fn reopen(&self, index: usize) -> Result<Box<dyn Source>> {
+        Ok(Box::new(ManagerSource::open(index)?))
+    }
+}
+
+impl From<&battery::Battery> for Reading {
+    fn from(battery: &battery::Battery) -> Self {
+        Reading {
+            vendor: battery.vendor().map(|v| v.trim().to_string()),
+            model: battery.model().map(|m| m.trim().to_string()),
+            serial_number: battery.serial_number().map(|s| s.trim().to_string()),
+            technology: battery.technology().into(),
+            state: battery.state().into(),
+            state_of_charge: battery.state_of_charge().get::<percent>(),
+            state_of_health: battery.state_of_health().get::<percent>(),
+            temperature: battery.temperature().map(|t| t.get::<degree_celsius>()),
+            cycle_count: battery.cycle_count(),
+            energy: battery.energy().get::<watt_hour>(),
+            energy_full: battery.energy_full().get::<watt_hour>(),
+            energy_full_design: battery.energy_full_design().get::<watt_hour>(),
+            energy_rate: battery.energy_rate().get::<watt>(),
+            voltage: battery.voltage().get::<volt>(),
+            time_to_empty: battery.time_to_empty().map(|t| t.value),
+            time_to_full: battery.time_to_full().map(|t| t.value),
+        }
+    }
+}
@@ -0,0 +1,164 @@
+//! Sources that battery information is read from.
+//!
+//! A `Battery` reads its values through the `Source` trait, so the same Python API works
+//! whether the values come from the platform battery API, a sysfs tree, a recording, or a
+//! virtual battery scripted by a test.
+
+mod fake;
+mod fixed;
+mod manager;
+mod replay;
+mod sysfs;
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::enums::{BatteryState, BatteryTechnology};
+use crate::error::Result;
+
+pub use fake::{FakeSource, FakeState};
+pub use fixed::FixedSource;
+pub use manager::ManagerSource;
+pub use replay::ReplaySource;
+pub use sysfs::SysfsSource;
+
+/// The raw values read from a battery, in the units used throughout the crate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reading {
+    /// The vendor of the battery.
+    pub vendor: Option<String>,
+    /// The model of the battery.
+    pub model: Option<String>,
+    /// The serial number of the battery.
+    pub serial_number: Option<String>,
+    /// The technology of the battery.
+    pub technology: BatteryTechnology,
+    /// The state of the battery (charging, discharging, etc.).
+    pub state: BatteryState,
+    /// The percentage of the battery that is full.
+    pub state_of_charge: f32,
+    /// The capacity of the battery, as a percentage of the design energy.
+    pub state_of_health: f32,
+    /// The temperature of the battery in degrees Celsius.
+    pub temperature: Option<f32>,
+    /// The cycle count of the battery.
+    pub cycle_count: Option<u32>,
+    /// The current energy of the battery in watt-hours.
+    pub energy: f32,
+    /// The full energy of the battery in watt-hours.
+    pub energy_full: f32,
+    /// The design energy of the battery in watt-hours.
+    pub energy_full_design: f32,
+    /// The energy rate of the battery in watts.
+    pub energy_rate: f32,
+    /// The voltage of the battery in volts.
+    pub voltage: f32,
+    /// The time to empty the battery in seconds.
+    pub time_to_empty: Option<f32>,
+    /// The time to fully charge the battery in seconds.
+    pub time_to_full: Option<f32>,
+}
+
+/// A source of battery readings for a single battery.
+pub trait Source: Send + fmt::Debug {
+    /// Returns the index of the battery this source reads.
+    fn index(&self) -> usize;
+
+    /// Refreshes the battery and returns its current values.
+    fn read(&mut self) -> Result<Reading>;
+
+    /// Opens the battery at another index through the same backend.
+    fn reopen(&self, index: usize) -> Result<Box<dyn Source>>;
+}
+
+/// Selects where batteries are read from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Backend {
+    /// The platform battery API.
+    Manager,
+    /// The Linux sysfs tree mounted at the given root, e.g. `/sys`.
+    Sysfs(PathBuf),
+}
+
+impl Backend {
+    /// Returns the sysfs backend if a root is given, and the platform battery API otherwise.
+    pub fn new(sysfs_root: Option<&Path>) -> Self {
+        match sysfs_root {
+            Some(root) => Backend::Sysfs(root.to_path_buf()),
+            None => Backend::Manager,
+        }
+    }
+
+    /// Opens the battery at the given index.
+    pub fn open(&self, index: usize) -> Result<Box<dyn Source>> {
+        match self {
+            Backend::Manager => Ok(Box::new(ManagerSource::open(index)?)),
+            Backend::Sysfs(root) => Ok(Box::new(SysfsSource::open(root, index)?)),
+        }
+    }
+
+    /// Opens every battery, in index order, enumerating them once.
+    pub fn open_all(&self) -> Result<Vec<Box<dyn Source>>> {
+        match self {
+            Backend::Manager => Ok(ManagerSource::all()?
+                .into_iter()
+                .map(|source| Box::new(source) as Box<dyn Source>)
+                .collect()),
+            Backend::Sysfs(root) => Ok(SysfsSource::all(root)?
+                .into_iter()
+                .map(|source| Box::new(source) as Box<dyn Source>)
+                .collect()),
+        }
+    }
+
+    /// Returns the number of batteries available through this backend.
+    pub fn count(&self) -> Result<usize> {
+        match self {
+            Backend::Manager => ManagerSource::count(),
+            Backend::Sysfs(root) => SysfsSource::count(root),
+        }
+    }
+}
+
+/// Estimates the time to empty and the time to full, in seconds, from the energy values.
+///
+/// Only the estimate that matches the state is returned, and neither is returned when the
+/// energy rate is zero.
+pub fn time_estimates(
+    state: BatteryState,
+    energy: f32,
+    energy_full: f32,
+    energy_rate: f32,
+) -> (Option<f32>, Option<f32>) {
+    let seconds_at_rate = |energy: f32| (energy_rate > 0.0).then(|| energy / energy_rate * 3600.0);
+    match state {
+        BatteryState::Discharging => (seconds_at_rate(energy), None),
+        BatteryState::Charging => (None, seconds_at_rate((energy_full - energy).max(0.0))),
+        _ => (None, None),
+    }
+}
+
+/// Parses a state name, as reported by sysfs (e.g. `Not charging`) or by `BatteryState`.
+pub fn parse_state(state: &str) -> BatteryState {
+    match state {
+        "Charging" => BatteryState::Charging,
+        "Discharging" => BatteryState::Discharging,
+        "Full" => BatteryState::Full,
+        "Empty" => BatteryState::Empty,
+        "Not charging" | "NotCharging" => BatteryState::NotCharging,
+        _ => BatteryState::Unknown,
+    }
+}
+
+/// Parses a technology name, as reported by sysfs (e.g. `Li-ion`) or by `BatteryTechnology`.
+pub fn parse_technology(technology: &str) -> BatteryTechnology {
+    match technology {
+        "Li-ion" | "LiIon" => BatteryTechnology::LiIon,
+        "Li-poly" | "LiPo" => BatteryTechnology::LiPo,
+        "LiFe" | "LiFePO4" => BatteryTechnology::LiFePO4,
+        "NiMH" => BatteryTechnology::NiMH,
+        "NiCd" => BatteryTechnology::NiCd,
+        "LeadAcid" => BatteryTechnology::LeadAcid,
+        _ => BatteryTechnology::Unknown,
+    }
+}
@@ -0,0 +1,119 @@
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use super::{Reading, Source};
+use crate::error::{Error, Result};
+use crate::record::{Record, read_records};
+
+/// Replays a battery recording made with `Battery.start_recording`.
+///
+/// In timed mode, each read returns the record that was current at the same point in the
+/// recording, measured from the first read and scaled by `speed`. In step mode (no speed), each
+/// read returns the next record. Either way the last record is repeated once the recording
+/// ends, unless `looping` starts it over.
+#[derive(Debug)]
+pub struct ReplaySource {
+    /// The path of the recording.
+    path: PathBuf,
+    /// The records of the battery being replayed, in recording order.
+    records: Vec<Record>,
+    /// The index of the battery being replayed.
+    index: usize,
+    /// How many times faster than real time to replay, or `None` to step one record per read.
+    speed: Option<f64>,
+    /// Whether to start over at the end of the recording.
+    looping: bool,
+    /// When the first read happened, in timed mode.
+    started: Option<Instant>,
+    /// The record the next read returns, in step mode.
+    position: usize,
+}
+
+impl ReplaySource {
+    /// Opens a recording.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The JSON Lines file to replay.
+    /// * `index` - The battery to replay, for recordings that hold several batteries. Defaults
+    ///   to the battery of the first record.
+    /// * `speed` - How many times faster than real time to replay, or `None` to step one record
+    ///   per read.
+    /// * `looping` - Whether to start over at the end of the recording.
+    pub fn open(
+        path: &Path,
+        index: Option<usize>,
+        speed: Option<f64>,
+        looping: bool,
+    ) -> Result<Self> {
+        let records = read_records(path)?;
+        let first = records.first().ok_or(Error::NoBatteries)?;
+        let index = index.unwrap_or(first.index);
+        let records: Vec<Record> = records
+            .into_iter()
+            .filter(|record| record.index == index)
+            .collect();
+        if records.is_empty() {
+            return Err(Error::IndexOutOfRange);
+        }
+        Ok(ReplaySource {
+            path: path.to_path_buf(),
+            records,
+            index,
+            speed,
+            looping,
+            started: None,
+            position: 0,
+        })
+    }
+
+    /// Returns the position of the record to replay in timed mode.
+    fn timed_position(&mut self, speed: f64) -> usize {
+        let started = *self.started.get_or_insert_with(Instant::now);
+        let first = self.records[0].timestamp;
+        let duration = self.records[self.records.len() - 1].timestamp - first;
+        let mut elapsed = started.elapsed().as_secs_f64() * speed;
+        if self.looping && duration > 0.0 {
+            elapsed %= duration;
+        }
+        self.records
+            .partition_point(|record| record.timestamp - first <= elapsed)
+            .saturating_sub(1)
+    }
+
+    /// Returns the position of the record to replay in step mode, and moves to the next one.
+    fn step_position(&mut self) -> usize {
+        let position = self.position;
+        self.position = if position + 1 < self.records.len() {
+            position + 1
+        } else if self.looping {
+            0
+        } else {
+            position
+        };
+        position
+    }
+}
+
+impl Source for ReplaySource {
+    fn index(&self) -> usize {
+        self.index
+    }
+
+    fn read(&mut self) -> Result<Reading> {
+        let position = match self.speed {
+            Some(speed) => self.timed_position(speed),
+            None => self.step_position(),
+        };
+        Ok(self.records[position].reading.clone())
+    }
+
+    fn reopen(&self, index: usize) -> Result<Box<dyn Source>> {
+        Ok(Box::new(ReplaySource::open(
+            &self.path,
+            Some(index),
+            self.speed,
+            self.looping,
+        )?))
+    }
+}
@@ -0,0 +1,169 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{Reading, Source, parse_state, parse_technology, time_estimates};
+use crate::enums::BatteryTechnology;
+use crate::error::{Error, Result};
+
+/// The directory under the sysfs root that lists power supplies.
+const POWER_SUPPLY_DIR: &str = "class/power_supply";
+
+/// Reads a battery directly from a Linux sysfs tree.
+///
+/// The tree does not have to be mounted at `/sys`: a host's sysfs bind-mounted into a
+/// container, or a directory of fixture files, works the same way. Values are read from
+/// `<root>/class/power_supply/<name>/`, following the kernel's power supply class ABI.
+#[derive(Debug)]
+pub struct SysfsSource {
+    /// The sysfs root, e.g. `/sys`.
+    root: PathBuf,
+    /// The power supply directory of the battery.
+    path: PathBuf,
+    /// The index of the battery among the batteries under the root.
+    index: usize,
+}
+
+impl SysfsSource {
+    /// Opens the battery at the given index under a sysfs root.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The sysfs root, e.g. `/sys`.
+    /// * `index` - The index of the battery to open. Batteries are ordered by name.
+    pub fn open(root: &Path, index: usize) -> Result<Self> {
+        let batteries = SysfsSource::batteries(root)?;
+        if batteries.is_empty() {
+            return Err(Error::NoBatteries);
+        }
+        let path = batteries
+            .into_iter()
+            .nth(index)
+            .ok_or(Error::IndexOutOfRange)?;
+        Ok(SysfsSource {
+            root: root.to_path_buf(),
+            path,
+            index,
+        })
+    }
+
+    /// Opens every battery under a sysfs root, in index order.
+    pub fn all(root: &Path) -> Result<Vec<Self>> {
+        Ok(SysfsSource::batteries(root)?
+            .into_iter()
+            .enumerate()
+            .map(|(index, path)| SysfsSource {
+                root: root.to_path_buf(),
+                path,
+                index,
+            })
+            .collect())
+    }
+
+    /// Returns the number of batteries under a sysfs root.
+    pub fn count(root: &Path) -> Result<usize> {
+        Ok(SysfsSource::batteries(root)?.len())
+    }
+
+    /// Lists the power supply directories of type `Battery` under a sysfs root, sorted by name.
+    fn batteries(root: &Path) -> Result<Vec<PathBuf>> {
+        let dir = root.join(POWER_SUPPLY_DIR);
+        let entries = fs::read_dir(&dir).map_err(|e| Error::Io(dir.clone(), e))?;
+        let mut batteries: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| read_string(path, "type").as_deref() == Some("Battery"))
+            .collect();
+        batteries.sort();
+        Ok(batteries)
+    }
+}
+
+impl Source for SysfsSource {
+    fn index(&self) -> usize {
+        self.index
+    }
+
+    fn read(&mut self) -> Result<Reading> {
+        let path = &self.path;
+        let status_path = path.join("status");
+        let status = fs::read_to_string(&status_path).map_err(|e| Error::Io(status_path, e))?;
+        let state = parse_state(status.trim());
+
+        // Energy values are reported either directly in µWh, or as charge in µAh that has to be
+        // multiplied by a voltage in µV.
+        let voltage = read_micro(path, "voltage_now").unwrap_or(0.0);
+        let design_voltage = read_micro(path, "voltage_min_design")
+            .or_else(|| read_micro(path, "voltage_max_design"))
+            .unwrap_or(voltage);
+        let energy_from = |energy: &str, charge: &str| {
+            read_micro(path, energy)
+                .or_else(|| read_micro(path, charge).map(|charge| charge * design_voltage))
+                .unwrap_or(0.0)
+        };
+        let energy = energy_from("energy_now", "charge_now");
+        let energy_full = energy_from("energy_full", "charge_full");
+        let energy_full_design = energy_from("energy_full_design", "charge_full_design");
+        let energy_rate = read_micro(path, "power_now")
+            .or_else(|| read_micro(path, "current_now").map(|current| current * voltage))
+            .unwrap_or(0.0)
+            .abs();
+
+        let state_of_charge = read_number(path, "capacity")
+            .or_else(|| ratio(energy, energy_full))
+            .unwrap_or(0.0)
+            .min(100.0);
+        let state_of_health = ratio(energy_full, energy_full_design).unwrap_or(100.0);
+
+        let (time_to_empty, time_to_full) = time_estimates(state, energy, energy_full, energy_rate);
+
+        Ok(Reading {
+            vendor: read_string(path, "manufacturer"),
+            model: read_string(path, "model_name"),
+            serial_number: read_string(path, "serial_number"),
+            technology: read_string(path, "technology")
+                .map(|technology| parse_technology(&technology))
+                .unwrap_or(BatteryTechnology::Unknown),
+            state,
+            state_of_charge,
+            state_of_health,
+            temperature: read_number(path, "temp").map(|temp| temp / 10.0),
+            cycle_count: read_number(path, "cycle_count")
+                .map(|count| count as u32)
+                .filter(|&count| count > 0),
+            energy,
+            energy_full,
+            energy_full_design,
+            energy_rate,
+            voltage,
+            time_to_empty,
+            time_to_full,
+        })
+    }
+
+    fn reopen(&self, index: usize) -> Result<Box<dyn Source>> {
+        Ok(Box::new(SysfsSource::open(&self.root, index)?))
+    }
+}
+
+/// Reads a trimmed attribute, treating a missing or empty file as absent.
+fn read_string(path: &Path, name: &str) -> Option<String> {
+    fs::read_to_string(path.join(name))
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Reads a numeric attribute.
+fn read_number(path: &Path, name: &str) -> Option<f32> {
+    read_string(path, name)?.parse().ok()
+}
+
+/// Reads a numeric attribute reported in micro-units (µV, µA, µW, µWh, µAh) and converts it to
+/// base units.
+fn read_micro(path: &Path, name: &str) -> Option<f32> {
+    read_number(path, name).map(|value| value / 1_000_000.0)
+}
+
+/// Returns `part` as a percentage of `whole`, if `whole` is non-zero.
+fn ratio(part: f32, whole: f32) -> Option<f32> {
+    (whole > 0.0).then(|| part / whole * 100.0)
+}
@@ -0,0 +1,260 @@
+//! Rendering battery information for status bars.
+//!
+//! The text shows an icon for the state, the percentage, and the time to empty or to full.
+//! While the battery is not on external power, it is shown at a low or critical level when the
+//! percentage is at or below the configured thresholds, with its own icon, CSS class and
+//! color.
+
+use std::collections::HashMap;
+
+use serde_json::json;
+
+use crate::enums::{BatteryState, StatusBar};
+use crate::error::{Error, Result};
+use crate::snapshot::BatterySnapshot;
+
+/// The names of the icons: one per state, and one per level.
+pub const ICON_NAMES: [&str; 8] = [
+    "charging",
+    "discharging",
+    "full",
+    "not_charging",
+    "empty",
+    "unknown",
+    "low",
+    "critical",
+];
+
+/// The names of the levels that have their own CSS class and color.
+pub const LEVEL_NAMES: [&str; 2] = ["low", "critical"];
+
+/// How a battery is shown at the low and critical levels, and the icons for each state.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatusStyle {
+    /// The percentage at or below which the battery is low.
+    pub low: f32,
+    /// The percentage at or below which the battery is critical.
+    pub critical: f32,
+    /// The icons, by name in `ICON_NAMES`.
+    pub icons: HashMap<String, String>,
+    /// The Waybar CSS classes of the levels, by name in `LEVEL_NAMES`.
+    pub classes: HashMap<String, String>,
+    /// The colors of the levels for i3blocks, i3bar, Polybar and tmux, by name in
+    /// `LEVEL_NAMES`.
+    pub colors: HashMap<String, String>,
+}
+
+impl Default for StatusStyle {
+    fn default() -> Self {
+        let map = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect()
+        };
+        StatusStyle {
+            low: 25.0,
+            critical: 10.0,
+            icons: map(&[
+                ("charging", "⇡"),
+                ("discharging", "⇣"),
+                ("full", "✓"),
+                ("not_charging", "•"),
+                ("empty", "✗"),
+                ("unknown", "?"),
+                ("low", "⇣"),
+                ("critical", "⚠"),
+            ]),
+            classes: map(&[("low", "warning"), ("critical", "critical")]),
+            colors: map(&[("low", "#FFB52A"), ("critical", "#FF5555")]),
+        }
+    }
+}
+
+impl StatusStyle {
+    /// Creates a style from the defaults and the given changes.
+    ///
+    /// # Arguments
+    ///
+    /// * `low` - The percentage at or below which the battery is low.
+    /// * `critical` - The percentage at or below which the battery is critical.
+    /// * `icons` - Icons to replace, by name in `ICON_NAMES`.
+    /// * `classes` - CSS classes to replace, by name in `LEVEL_NAMES`.
+    /// * `colors` - Colors to replace, by name in `LEVEL_NAMES`.
+    pub fn new(
+        low: f32,
+        critical: f32,
+        icons: HashMap<String, String>,
+        classes: HashMap<String, String>,
+        colors: HashMap<String, String>,
+    ) -> Result<Self> {
+        if critical > low {
+            return Err(Error::InvalidStyle(format!(
+                "the critical level ({}) is above the low level ({})",
+                critical, low
+            )));
+        }
+        let mut style = StatusStyle {
+            low,
+            critical,
+            ..StatusStyle::default()
+        };
+        replace(&mut style.icons, icons, &ICON_NAMES, "icon")?;
+        replace(&mut style.classes, classes, &LEVEL_NAMES, "level")?;
+        replace(&mut style.colors, colors, &LEVEL_NAMES, "level")?;
+        Ok(style)
+    }
+
+    /// Returns the level of a battery: `low`, `critical`, or `None` when neither.
+    ///
+    /// A battery on external power is never low or critical.
+    pub fn level(&self, snapshot: &BatterySnapshot) -> Option<&'static str> {
+        match snapshot.percent.value {
+            _ if snapshot.state.on_external_power() => None,
+            percent if percent <= self.critical => Some("critical"),
+            percent if percent <= self.low => Some("low"),
+            _ => None,
+        }
+    }
+
+    /// Returns the icon of a battery: the icon of its level, or of its state.
+    pub fn icon(&self, snapshot: &BatterySnapshot) -> &str {
+        let name = self
+            .level(snapshot)
+            .unwrap_or_else(|| state_name(snapshot.state));
+        &self.icons[name]
+    }
+}
+
+/// Replaces some of the values of a map, checking that their names are known.
+fn replace(
+    values: &mut HashMap<String, String>,
+    changes: HashMap<String, String>,
+    names: &[&str],
+    kind: &str,
+) -> Result<()> {
+    for (name, value) in changes {
+        if !names.contains(&name.as_str()) {
+            return Err(Error::InvalidStyle(format!(
+                "unknown {} {:?} (expected one of {})",
+                kind,
+                name,
+                names.join(", ")
+            )));
+        }
+        values.insert(name, value);
+    }
+    Ok(())
+}
+
+/// Returns the name of a state in `ICON_NAMES`, also used as its Waybar CSS class.
+pub fn state_name(state: BatteryState) -> &'static str {
+    match state {
+        BatteryState::Charging => "charging",
+        BatteryState::Discharging => "discharging",
+        BatteryState::Full => "full",
+        BatteryState::NotCharging => "not_charging",
+        BatteryState::Empty => "empty",
+        BatteryState::Unknown => "unknown",
+    }
+}
+
+/// Returns the status text: the icon, the percentage, and the time to empty or to full.
+fn text(snapshot: &BatterySnapshot, style: &StatusStyle) -> String {
+    let mut text = format!("{} {}", style.icon(snapshot), snapshot.percent);
+    if let Some(time) = snapshot
+        .time_to_empty
+        .as_ref()
+        .or(snapshot.time_to_full.as_ref())
+    {
+        text += &format!(" {}", time);
+    }
+    text
+}
+
+/// Returns a longer description of the battery, one fact per line.
+fn tooltip(snapshot: &BatterySnapshot) -> String {
+    let name: Vec<&str> = [&snapshot.vendor, &snapshot.model]
+        .into_iter()
+        .flatten()
+        .map(String::as_str)
+        .collect();
+    let mut lines = vec![
+        format!("Battery {} {}", snapshot.index, name.join(" "))
+            .trim_end()
+            .to_string(),
+        format!("{}, {}", snapshot.state.name(), snapshot.percent),
+        format!("{}, {}", snapshot.energy, snapshot.energy_rate),
+    ];
+    if let Some(time) = &snapshot.time_to_empty {
+        lines.push(format!("{} to empty", time));
+    }
+    if let Some(time) = &snapshot.time_to_full {
+        lines.push(format!("{} to full", time));
+    }
+    lines.push(format!("Capacity {}", snapshot.capacity));
+    lines.join("\n")
+}
+
+/// Renders battery information for a status bar.
+///
+/// # Arguments
+///
+/// * `bar` - The status bar to render for.
+/// * `snapshot` - The battery information.
+/// * `style` - The thresholds, icons, CSS classes and colors.
+///
+/// # Returns
+///
+/// The output the status bar reads from a script, without a trailing newline.
+pub fn render(bar: StatusBar, snapshot: &BatterySnapshot, style: &StatusStyle) -> String {
+    let text = text(snapshot, style);
+    let level = style.level(snapshot);
+    let color = level.map(|level| style.colors[level].as_str());
+    match bar {
+        StatusBar::Waybar => {
+            let mut classes = vec![state_name(snapshot.state)];
+            classes.extend(level.map(|level| style.classes[level].as_str()));
+            json!({
+                "text": text,
+                "tooltip": tooltip(snapshot),
+                "class": classes,
+                "percentage": snapshot.percent.value.round() as i64,
+            })
+            .to_string()
+        }
+        StatusBar::I3blocks => {
+            let short_text = snapshot.percent.to_string();
+            format!("{}\n{}\n{}", text, short_text, color.unwrap_or_default())
+                .trim_end()
+                .to_string()
+        }
+        StatusBar::I3bar => {
+            let mut block = json!({
+                "name": "battery",
+                "instance": snapshot.index.to_string(),
+                "full_text": text,
+                "short_text": snapshot.percent.to_string(),
+            });
+            if let Some(color) = color {
+                block["color"] = json!(color);
+            }
+            if level == Some("critical") {
+                block["urgent"] = json!(true);
+            }
+            block.to_string()
+        }
+        StatusBar::Polybar => match color {
+            Some(color) => format!("%{{F{}}}{}%{{F-}}", color, text),
+            None => text,
+        },
+        StatusBar::Tmux => {
+            // `#` starts a format in tmux, so it is doubled to show it as is.
+            let text = text.replace('#', "##");
+            match color {
+                Some(color) => format!("#[fg={}]{}#[default]", color, text),
+                None => text,
+            }
+        }
+    }
+}
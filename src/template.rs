@@ -0,0 +1,619 @@
+//! A small template language for formatting battery information.
+//!
+//! A template is text with fields in braces, e.g. `{percent} {state_icon} {time_remaining|human}`.
+//! A field can be followed by filters separated by `|`, which are applied in order:
+//!
+//! * a time format name, e.g. `human`, `seconds` or `hours_minutes`, formats a time;
+//! * `precision:N` shows a measurement with `N` decimals;
+//! * `to:UNITS` converts a measurement, e.g. `to:kWh`;
+//! * `value` and `units` show only the value or the units of a measurement;
+//! * `icon:A,B,C` picks an icon by percentage, the first for the lowest;
+//! * `default:TEXT` shows `TEXT` when the field has no value;
+//! * `upper` and `lower` change the case of the text.
+//!
+//! `{if CONDITION}`, `{elif CONDITION}`, `{else}` and `{end}` show parts of the template
+//! depending on the battery. A condition is a state (`charging`, `discharging`, `full`,
+//! `not_charging`, `empty`, `unknown`), a level (`low`, `critical`), `on_ac`, or a field name,
+//! which holds when the field has a value. Conditions can be negated with `not`. Literal braces
+//! are written `{{` and `}}`.
+
+use std::vec::IntoIter;
+
+use crate::display::DisplaySettings;
+use crate::duration::format_time;
+use crate::enums::TimeFormat;
+use crate::error::{Error, Result};
+use crate::measurement::Measurement;
+use crate::snapshot::BatterySnapshot;
+use crate::status::{ICON_NAMES, LEVEL_NAMES, StatusStyle, state_name};
+use crate::units::{Unit, convert};
+
+/// The kind of value of a field, which decides the filters it accepts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    /// Plain text.
+    Text,
+    /// A measurement, in units of the same quantity as the given units.
+    Measurement(&'static str),
+    /// A time in seconds.
+    Time,
+}
+
+/// The fields that can be used in a template.
+const FIELDS: [(&str, Kind); 23] = [
+    ("index", Kind::Text),
+    ("battery_index", Kind::Text),
+    ("vendor", Kind::Text),
+    ("model", Kind::Text),
+    ("serial_number", Kind::Text),
+    ("technology", Kind::Text),
+    ("state", Kind::Text),
+    ("state_icon", Kind::Text),
+    ("cycle_count", Kind::Text),
+    ("timestamp", Kind::Text),
+    ("percent", Kind::Measurement("%")),
+    ("capacity", Kind::Measurement("%")),
+    ("temperature", Kind::Measurement("°C")),
+    ("energy", Kind::Measurement("Wh")),
+    ("energy_full", Kind::Measurement("Wh")),
+    ("energy_full_design", Kind::Measurement("Wh")),
+    ("energy_rate", Kind::Measurement("W")),
+    ("voltage", Kind::Measurement("V")),
+    ("time_to_empty", Kind::Time),
+    ("time_to_full", Kind::Time),
+    ("time_remaining", Kind::Time),
+    ("time_to_empty_seconds", Kind::Text),
+    ("time_to_full_seconds", Kind::Text),
+];
+
+/// A filter applied to the value of a field.
+#[derive(Debug, Clone, PartialEq)]
+enum Filter {
+    /// Formats a time.
+    Time(TimeFormat),
+    /// Changes the number of decimals of a measurement.
+    Precision(usize),
+    /// Converts a measurement to other units.
+    To(&'static str),
+    /// Shows the value of a measurement without units.
+    Value,
+    /// Shows the units of a measurement.
+    Units,
+    /// Picks an icon by percentage.
+    Icon(Vec<String>),
+    /// Shows a text when there is no value.
+    Default(String),
+    /// Changes the text to uppercase.
+    Upper,
+    /// Changes the text to lowercase.
+    Lower,
+}
+
+/// A condition of an `{if}` or `{elif}` tag.
+#[derive(Debug, Clone, PartialEq)]
+enum Condition {
+    /// The battery is in a state, by name in `ICON_NAMES`.
+    State(&'static str),
+    /// The battery is at a level or below, by name in `LEVEL_NAMES`.
+    Level(&'static str),
+    /// The battery is on external power.
+    OnAc,
+    /// The field has a value.
+    Present(&'static str),
+    /// The condition does not hold.
+    Not(Box<Condition>),
+}
+
+/// A part of a template.
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    /// Text shown as is.
+    Text(String),
+    /// A field and its filters.
+    Field(&'static str, Vec<Filter>),
+    /// The body of the first condition that holds, or `otherwise` when none does.
+    If {
+        branches: Vec<(Condition, Vec<Node>)>,
+        otherwise: Vec<Node>,
+    },
+}
+
+/// A part of a template before it is parsed.
+#[derive(Debug)]
+enum Token {
+    /// Text, with the doubled braces unescaped.
+    Text(String),
+    /// The content of a tag, between braces.
+    Tag(String),
+}
+
+/// The tag that ends a block of a template.
+#[derive(Debug)]
+enum Closing {
+    Elif(Condition),
+    Else,
+    End,
+}
+
+/// The value of a field while filters are applied.
+#[derive(Debug, Clone)]
+enum Value {
+    Text(String),
+    Measurement(Measurement),
+    Time(f64),
+    Missing,
+}
+
+/// A parsed template.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+impl Template {
+    /// Parses a template, checking its fields, filters and conditions.
+    pub fn parse(template: &str) -> Result<Template> {
+        let mut tokens = tokenize(template)?.into_iter();
+        match parse_block(&mut tokens)? {
+            (nodes, None) => Ok(Template { nodes }),
+            (_, Some(Closing::Elif(_))) => Err(invalid("{elif} without {if}")),
+            (_, Some(Closing::Else)) => Err(invalid("{else} without {if}")),
+            (_, Some(Closing::End)) => Err(invalid("{end} without {if}")),
+        }
+    }
+
+    /// Renders the template for a battery.
+    ///
+    /// # Arguments
+    ///
+    /// * `snapshot` - The battery information.
+    /// * `display` - The settings for times shown without a time format filter.
+    /// * `style` - The icons of `state_icon`, and the thresholds of the `low` and `critical`
+    ///   conditions.
+    pub fn render(
+        &self,
+        snapshot: &BatterySnapshot,
+        display: &DisplaySettings,
+        style: &StatusStyle,
+    ) -> String {
+        let mut output = String::new();
+        render_nodes(&self.nodes, snapshot, display, style, &mut output);
+        output
+    }
+}
+
+/// Returns an `InvalidTemplate` error.
+fn invalid(message: impl Into<String>) -> Error {
+    Error::InvalidTemplate(message.into())
+}
+
+/// Splits a template into text and tags.
+fn tokenize(template: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut text = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.next_if_eq(&'{').is_some() => text.push('{'),
+            '}' if chars.next_if_eq(&'}').is_some() => text.push('}'),
+            '{' => {
+                let mut tag = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some('{') => return Err(invalid(format!("{{ inside {{{}}}", tag))),
+                        Some(c) => tag.push(c),
+                        None => return Err(invalid(format!("unclosed {{{}", tag))),
+                    }
+                }
+                if !text.is_empty() {
+                    tokens.push(Token::Text(std::mem::take(&mut text)));
+                }
+                tokens.push(Token::Tag(tag));
+            }
+            '}' => return Err(invalid("single } (write }} for a brace)")),
+            c => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        tokens.push(Token::Text(text));
+    }
+    Ok(tokens)
+}
+
+/// Parses nodes up to the end of the template or the tag that ends the block.
+fn parse_block(tokens: &mut IntoIter<Token>) -> Result<(Vec<Node>, Option<Closing>)> {
+    let mut nodes = Vec::new();
+    while let Some(token) = tokens.next() {
+        let tag = match token {
+            Token::Text(text) => {
+                nodes.push(Node::Text(text));
+                continue;
+            }
+            Token::Tag(tag) => tag,
+        };
+        let tag = tag.trim();
+        let (keyword, rest) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+        match keyword {
+            "if" => nodes.push(parse_if(parse_condition(rest)?, tokens)?),
+            "elif" => return Ok((nodes, Some(Closing::Elif(parse_condition(rest)?)))),
+            "else" if rest.is_empty() => return Ok((nodes, Some(Closing::Else))),
+            "end" if rest.is_empty() => return Ok((nodes, Some(Closing::End))),
+            _ => nodes.push(parse_field(tag)?),
+        }
+    }
+    Ok((nodes, None))
+}
+
+/// Parses the branches of an `{if}` tag, up to its `{end}` tag.
+fn parse_if(condition: Condition, tokens: &mut IntoIter<Token>) -> Result<Node> {
+    let mut branches = Vec::new();
+    let mut condition = condition;
+    loop {
+        let (body, end) = parse_block(tokens)?;
+        branches.push((condition, body));
+        match end {
+            Some(Closing::Elif(next)) => condition = next,
+            Some(Closing::Else) => {
+                return match parse_block(tokens)? {
+                    (otherwise, Some(Closing::End)) => Ok(Node::If {
+                        branches,
+                        otherwise,
+                    }),
+                    (_, Some(_)) => Err(invalid("{else} must be the last branch of an {if}")),
+                    (_, None) => Err(invalid("{if} without {end}")),
+                };
+            }
+            Some(Closing::End) => {
+                return Ok(Node::If {
+                    branches,
+                    otherwise: Vec::new(),
+                });
+            }
+            None => return Err(invalid("{if} without {end}")),
+        }
+    }
+}
+
+/// Parses a condition, e.g. `charging` or `not on_ac`.
+fn parse_condition(text: &str) -> Result<Condition> {
+    let text = text.trim();
+    if let Some(("not", rest)) = text.split_once(char::is_whitespace) {
+        return Ok(Condition::Not(Box::new(parse_condition(rest)?)));
+    }
+    if let Some(name) = ICON_NAMES.iter().find(|name| **name == text) {
+        if LEVEL_NAMES.contains(name) {
+            return Ok(Condition::Level(name));
+        }
+        return Ok(Condition::State(name));
+    }
+    if text == "on_ac" {
+        return Ok(Condition::OnAc);
+    }
+    match FIELDS.iter().find(|(name, _)| *name == text) {
+        Some((name, _)) => Ok(Condition::Present(name)),
+        None if text.is_empty() => Err(invalid("missing condition")),
+        None => Err(invalid(format!("unknown condition {:?}", text))),
+    }
+}
+
+/// Parses a field and its filters, e.g. `energy|to:kWh|precision:2`.
+fn parse_field(tag: &str) -> Result<Node> {
+    let mut parts = tag.split('|');
+    let name = parts.next().unwrap_or_default().trim();
+    let (name, mut kind) = FIELDS
+        .iter()
+        .find(|(field, _)| *field == name)
+        .copied()
+        .ok_or_else(|| match name {
+            "" => invalid("empty field {}"),
+            name => invalid(format!("unknown field {:?}", name)),
+        })?;
+    let mut filters = Vec::new();
+    for text in parts {
+        let filter = parse_filter(text)?;
+        kind = apply_kind(&filter, kind)
+            .ok_or_else(|| invalid(format!("filter {:?} cannot be applied to {:?}", text, name)))?;
+        filters.push(filter);
+    }
+    Ok(Node::Field(name, filters))
+}
+
+/// Parses a filter, e.g. `precision:2`.
+fn parse_filter(text: &str) -> Result<Filter> {
+    let (name, argument) = match text.split_once(':') {
+        Some((name, argument)) => (name.trim(), Some(argument)),
+        None => (text.trim(), None),
+    };
+    match (name, argument) {
+        ("precision", Some(decimals)) => decimals
+            .trim()
+            .parse()
+            .map(Filter::Precision)
+            .map_err(|_| invalid(format!("precision {:?} is not a number", decimals))),
+        ("to", Some(units)) => Ok(Filter::To(Unit::find(units.trim())?.symbol)),
+        ("icon", Some(icons)) => Ok(Filter::Icon(icons.split(',').map(String::from).collect())),
+        ("default", Some(text)) => Ok(Filter::Default(text.to_string())),
+        ("value", None) => Ok(Filter::Value),
+        ("units", None) => Ok(Filter::Units),
+        ("upper", None) => Ok(Filter::Upper),
+        ("lower", None) => Ok(Filter::Lower),
+        (name, None) => TimeFormat::find(&name.replace('_', ""))
+            .map(Filter::Time)
+            .ok_or_else(|| invalid(format!("unknown filter {:?}", name))),
+        (name, Some(_)) => Err(invalid(format!("unknown filter {:?}", name))),
+    }
+}
+
+/// Returns the kind of value a filter gives, or `None` if it does not accept the kind.
+fn apply_kind(filter: &Filter, kind: Kind) -> Option<Kind> {
+    match (filter, kind) {
+        (Filter::Time(_), Kind::Time) => Some(Kind::Text),
+        (Filter::Precision(_), Kind::Measurement(_)) => Some(kind),
+        (Filter::To(to), Kind::Measurement(from)) => {
+            convert(0.0, from, to).ok().map(|_| Kind::Measurement(to))
+        }
+        (Filter::Value | Filter::Units, Kind::Measurement(_)) => Some(Kind::Text),
+        (Filter::Icon(_), Kind::Measurement("%")) => Some(Kind::Text),
+        (Filter::Default(_) | Filter::Upper | Filter::Lower, _) => Some(Kind::Text),
+        _ => None,
+    }
+}
+
+/// Renders nodes, appending them to `output`.
+fn render_nodes(
+    nodes: &[Node],
+    snapshot: &BatterySnapshot,
+    display: &DisplaySettings,
+    style: &StatusStyle,
+    output: &mut String,
+) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.push_str(text),
+            Node::Field(name, filters) => {
+                let value = filters
+                    .iter()
+                    .fold(field_value(name, snapshot, style), |value, filter| {
+                        apply(filter, value, display)
+                    });
+                output.push_str(&text(value, display));
+            }
+            Node::If {
+                branches,
+                otherwise,
+            } => {
+                let body = branches
+                    .iter()
+                    .find(|(condition, _)| holds(condition, snapshot, style))
+                    .map_or(otherwise, |(_, body)| body);
+                render_nodes(body, snapshot, display, style, output);
+            }
+        }
+    }
+}
+
+/// Returns whether a condition holds for a battery.
+fn holds(condition: &Condition, snapshot: &BatterySnapshot, style: &StatusStyle) -> bool {
+    match condition {
+        Condition::State(name) => state_name(snapshot.state) == *name,
+        // A critical battery is also low.
+        Condition::Level(level) => match style.level(snapshot) {
+            Some("critical") => true,
+            current => current == Some(*level),
+        },
+        Condition::OnAc => snapshot.state.on_external_power(),
+        Condition::Present(name) => !matches!(field_value(name, snapshot, style), Value::Missing),
+        Condition::Not(condition) => !holds(condition, snapshot, style),
+    }
+}
+
+/// Returns the value of a field in `FIELDS`.
+fn field_value(name: &str, snapshot: &BatterySnapshot, style: &StatusStyle) -> Value {
+    let text = |text: &Option<String>| text.clone().map_or(Value::Missing, Value::Text);
+    let time = |seconds: Option<f64>| seconds.map_or(Value::Missing, Value::Time);
+    match name {
+        "index" | "battery_index" => Value::Text(snapshot.index.to_string()),
+        "vendor" => text(&snapshot.vendor),
+        "model" => text(&snapshot.model),
+        "serial_number" => text(&snapshot.serial_number),
+        "technology" => Value::Text(snapshot.technology.description().to_string()),
+        "state" => Value::Text(snapshot.state.name().to_string()),
+        "state_icon" => Value::Text(style.icon(snapshot).to_string()),
+        "cycle_count" => text(&snapshot.cycle_count.map(|count| count.to_string())),
+        "timestamp" => Value::Text(snapshot.timestamp.to_string()),
+        "percent" => Value::Measurement(snapshot.percent.clone()),
+        "capacity" => Value::Measurement(snapshot.capacity.clone()),
+        "temperature" => snapshot
+            .temperature
+            .clone()
+            .map_or(Value::Missing, Value::Measurement),
+        "energy" => Value::Measurement(snapshot.energy.clone()),
+        "energy_full" => Value::Measurement(snapshot.energy_full.clone()),
+        "energy_full_design" => Value::Measurement(snapshot.energy_full_design.clone()),
+        "energy_rate" => Value::Measurement(snapshot.energy_rate.clone()),
+        "voltage" => Value::Measurement(snapshot.voltage.clone()),
+        "time_to_empty" => time(snapshot.time_to_empty_seconds),
+        "time_to_full" => time(snapshot.time_to_full_seconds),
+        "time_remaining" => time(
+            snapshot
+                .time_to_empty_seconds
+                .or(snapshot.time_to_full_seconds),
+        ),
+        "time_to_empty_seconds" => text(&snapshot.time_to_empty_seconds.map(|s| s.to_string())),
+        "time_to_full_seconds" => text(&snapshot.time_to_full_seconds.map(|s| s.to_string())),
+        _ => unreachable!("unknown field {:?}", name),
+    }
+}
+
+/// Applies a filter to a value whose kind it accepts.
+fn apply(filter: &Filter, value: Value, display: &DisplaySettings) -> Value {
+    match (filter, value) {
+        (Filter::Default(text), Value::Missing) => Value::Text(text.clone()),
+        (_, Value::Missing) => Value::Missing,
+        (Filter::Time(format), Value::Time(seconds)) => {
+            Value::Text(format_time(seconds as f32, *format, &display.locale()).to_string())
+        }
+        (Filter::Precision(decimals), Value::Measurement(measurement)) => {
+            Value::Measurement(Measurement {
+                decimals: *decimals,
+                ..measurement
+            })
+        }
+        (Filter::To(units), Value::Measurement(measurement)) => Value::Measurement(
+            measurement
+                .convert_to(units)
+                .expect("units are checked when parsing"),
+        ),
+        (Filter::Value, Value::Measurement(measurement)) => Value::Text(measurement.format_value()),
+        (Filter::Units, Value::Measurement(measurement)) => Value::Text(measurement.units),
+        (Filter::Icon(icons), Value::Measurement(measurement)) => {
+            let share = (measurement.value / 100.0).clamp(0.0, 1.0);
+            let index = ((share * icons.len() as f32) as usize).min(icons.len() - 1);
+            Value::Text(icons[index].clone())
+        }
+        (Filter::Upper, value) => Value::Text(text(value, display).to_uppercase()),
+        (Filter::Lower, value) => Value::Text(text(value, display).to_lowercase()),
+        (Filter::Default(_), value) => Value::Text(text(value, display)),
+        // Other combinations are rejected when parsing.
+        (_, value) => value,
+    }
+}
+
+/// Returns the text of a value, showing times in the configured time format.
+fn text(value: Value, display: &DisplaySettings) -> String {
+    match value {
+        Value::Text(text) => text,
+        Value::Measurement(measurement) => measurement.to_string(),
+        Value::Time(seconds) => {
+            format_time(seconds as f32, display.time_format, &display.locale()).to_string()
+        }
+        Value::Missing => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enums::{BatteryState, BatteryTechnology, TempUnit};
+    use crate::source::Reading;
+
+    /// Returns a discharging battery at 81%, with 1.5 hours left.
+    fn reading() -> Reading {
+        Reading {
+            vendor: Some("SMP".to_string()),
+            model: Some("5YRYV71".to_string()),
+            serial_number: None,
+            technology: BatteryTechnology::LiIon,
+            state: BatteryState::Discharging,
+            state_of_charge: 81.0,
+            state_of_health: 91.2,
+            temperature: Some(31.2),
+            cycle_count: Some(87),
+            energy: 42.1,
+            energy_full: 52.0,
+            energy_full_design: 57.0,
+            energy_rate: 8.5,
+            voltage: 12.345,
+            time_to_empty: Some(5400.0),
+            time_to_full: None,
+        }
+    }
+
+    /// Renders a template for a reading.
+    fn render_reading(template: &str, reading: &Reading) -> String {
+        let display = DisplaySettings::new(TimeFormat::Human, TempUnit::DegC);
+        let snapshot = display.snapshot(0, reading, 0.0);
+        Template::parse(template)
+            .unwrap()
+            .render(&snapshot, &display, &StatusStyle::default())
+    }
+
+    /// Renders a template for the default reading.
+    fn render(template: &str) -> String {
+        render_reading(template, &reading())
+    }
+
+    /// Returns the message of the error from parsing a template.
+    fn error(template: &str) -> String {
+        match Template::parse(template) {
+            Err(Error::InvalidTemplate(message)) => message,
+            other => panic!("{:?} parsed as {:?}", template, other),
+        }
+    }
+
+    #[test]
+    fn renders_fields() {
+        assert_eq!(render("{vendor} {model}: {percent}"), "SMP 5YRYV71: 81.0%");
+        assert_eq!(render("{state} {index}"), "Discharging 0");
+        assert_eq!(render("{serial_number}"), "");
+    }
+
+    #[test]
+    fn applies_filters() {
+        assert_eq!(render("{energy|to:kWh|precision:2}"), "0.04 kWh");
+        assert_eq!(render("{voltage|value}|{voltage|units}"), "12.3|V");
+        assert_eq!(render("{temperature|to:F|precision:0}"), "88 °F");
+        assert_eq!(render("{time_remaining|hours_minutes}"), "1:30");
+        assert_eq!(render("{time_remaining|iso8601}"), "PT1H30M");
+        assert_eq!(render("{time_to_full|default:n/a}"), "n/a");
+        assert_eq!(render("{state|upper} {vendor|lower}"), "DISCHARGING smp");
+        assert_eq!(render("{percent|icon:a,b,c,d,e}"), "e");
+    }
+
+    #[test]
+    fn renders_conditions() {
+        let template = "{if charging}+{elif low}!{elif not on_ac}-{else}={end}";
+        assert_eq!(render(template), "-");
+        let low = Reading {
+            state_of_charge: 12.0,
+            ..reading()
+        };
+        assert_eq!(render_reading(template, &low), "!");
+        let charging = Reading {
+            state: BatteryState::Charging,
+            ..reading()
+        };
+        assert_eq!(render_reading(template, &charging), "+");
+        assert_eq!(
+            render("{if cycle_count}{cycle_count} cycles{end}"),
+            "87 cycles"
+        );
+        assert_eq!(render("{if serial_number}{serial_number}{end}"), "");
+    }
+
+    #[test]
+    fn escapes_braces() {
+        assert_eq!(render("{{percent}} {{{percent}}}"), "{percent} {81.0%}");
+        assert_eq!(render("}}"), "}");
+    }
+
+    #[test]
+    fn rejects_invalid_templates() {
+        assert_eq!(error("{percent"), "unclosed {percent");
+        assert_eq!(error("{per{cent}"), "{ inside {per}");
+        assert_eq!(error("percent}"), "single } (write }} for a brace)");
+        assert_eq!(error("{}"), "empty field {}");
+        assert_eq!(error("{speed}"), "unknown field \"speed\"");
+        assert_eq!(error("{percent|bold}"), "unknown filter \"bold\"");
+        assert_eq!(
+            error("{percent|precision:x}"),
+            "precision \"x\" is not a number"
+        );
+        assert_eq!(
+            error("{vendor|precision:1}"),
+            "filter \"precision:1\" cannot be applied to \"vendor\""
+        );
+        assert_eq!(error("{if raining}{end}"), "unknown condition \"raining\"");
+        assert_eq!(error("{if charging}"), "{if} without {end}");
+        assert_eq!(error("{else}"), "{else} without {if}");
+        assert_eq!(
+            error("{if low}{else}{elif critical}{end}"),
+            "{else} must be the last branch of an {if}"
+        );
+        assert!(matches!(
+            Template::parse("{energy|to:furlongs}"),
+            Err(Error::UnknownUnit(_))
+        ));
+    }
+}
@@ -0,0 +1,276 @@
+//! Helpers for testing applications that use `batteryinfo` on machines without a battery.
+
+use pyo3::prelude::*;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::battery::Battery;
+use crate::enums::{BatteryState, BatteryTechnology, Named, TempUnit, TimeFormat};
+use crate::source::{FakeSource, FakeState, parse_state, parse_technology};
+
+/// A virtual battery with scripted values.
+///
+/// `battery()` returns a normal `Battery` backed by the virtual battery. Changes made with
+/// `set()` and `step()` are seen by every `Battery` created from it on its next refresh.
+#[pyclass(module = "batteryinfo.testing")]
+#[derive(Debug)]
+pub struct FakeBattery {
+    /// The state shared with the sources of the batteries created from this fake.
+    state: Arc<Mutex<FakeState>>,
+    /// The index reported for the battery.
+    index: usize,
+}
+
+impl FakeBattery {
+    /// Locks the shared state.
+    fn lock(&self) -> MutexGuard<'_, FakeState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[pymethods]
+impl FakeBattery {
+    /// Creates a new virtual battery.
+    ///
+    /// # Arguments
+    ///
+    /// * `vendor` - The vendor of the battery (optional).
+    /// * `model` - The model of the battery (optional).
+    /// * `serial_number` - The serial number of the battery (optional).
+    /// * `technology` - The technology of the battery, as a `BatteryTechnology` or as named by
+    ///   sysfs, e.g. `Li-ion` (default: `LiIon`).
+    /// * `state` - The state of the battery, as a `BatteryState` or by name (default:
+    ///   `Discharging`).
+    /// * `energy` - The current energy in watt-hours (default: 40.0).
+    /// * `energy_full` - The full energy in watt-hours (default: 50.0).
+    /// * `energy_full_design` - The design energy in watt-hours (default: 50.0).
+    /// * `energy_rate` - The energy rate in watts (default: 10.0).
+    /// * `voltage` - The voltage in volts (default: 12.0).
+    /// * `temperature` - The temperature in degrees Celsius (optional).
+    /// * `cycle_count` - The cycle count (optional).
+    /// * `index` - The index reported for the battery (default: 0).
+    #[new]
+    #[pyo3(signature = (
+        vendor=None,
+        model=None,
+        serial_number=None,
+        technology=Named::Value(BatteryTechnology::LiIon),
+        state=Named::Value(BatteryState::Discharging),
+        energy=40.0,
+        energy_full=50.0,
+        energy_full_design=50.0,
+        energy_rate=10.0,
+        voltage=12.0,
+        temperature=None,
+        cycle_count=None,
+        index=0,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        vendor: Option<String>,
+        model: Option<String>,
+        serial_number: Option<String>,
+        technology: Named<BatteryTechnology>,
+        state: Named<BatteryState>,
+        energy: f32,
+        energy_full: f32,
+        energy_full_design: f32,
+        energy_rate: f32,
+        voltage: f32,
+        temperature: Option<f32>,
+        cycle_count: Option<u32>,
+        index: usize,
+    ) -> Self {
+        let state = FakeState {
+            vendor,
+            model,
+            serial_number,
+            technology: technology.resolve(parse_technology),
+            state: state.resolve(parse_state),
+            temperature,
+            cycle_count,
+            energy,
+            energy_full,
+            energy_full_design,
+            energy_rate,
+            voltage,
+            present: true,
+        };
+        FakeBattery {
+            state: Arc::new(Mutex::new(state)),
+            index,
+        }
+    }
+
+    /// Returns a `Battery` backed by this virtual battery.
+    ///
+    /// # Arguments
+    ///
+    /// * `time_format` - The format for displaying time (default: `TimeFormat::Human`).
+    /// * `temp_unit` - The unit for displaying temperature (default: `TempUnit::DegF`).
+    /// * `refresh_interval` - The interval for refreshing the battery information (default: 0
+    ///   ms, so every read sees the latest scripted values).
+    /// * `precision` - The number of decimals to display per field (default: 1 for every field).
+    /// * `locale` - The locale for displaying times and numbers (default: the global locale).
+    #[pyo3(signature = (
+        time_format=TimeFormat::Human,
+        temp_unit=TempUnit::DegF,
+        refresh_interval=0,
+        precision=None,
+        locale=None,
+    ))]
+    fn battery(
+        &self,
+        py: Python<'_>,
+        time_format: TimeFormat,
+        temp_unit: TempUnit,
+        refresh_interval: u64,
+        precision: Option<HashMap<String, usize>>,
+        locale: Option<String>,
+    ) -> PyResult<Battery> {
+        Battery::from_source(
+            py,
+            Box::new(FakeSource::new(self.state.clone(), self.index)),
+            time_format,
+            temp_unit,
+            Duration::from_millis(refresh_interval),
+        )?
+        .with_precision(precision)?
+        .with_locale(locale)
+    }
+
+    /// Changes the scripted values. Only the given values are changed.
+    ///
+    /// Setting `present` to `False` simulates removing the battery: refreshing a `Battery`
+    /// backed by it raises an error until it is set back to `True`.
+    #[pyo3(signature = (
+        vendor=None,
+        model=None,
+        serial_number=None,
+        technology=None,
+        state=None,
+        energy=None,
+        energy_full=None,
+        energy_full_design=None,
+        energy_rate=None,
+        voltage=None,
+        temperature=None,
+        cycle_count=None,
+        present=None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn set(
+        &self,
+        vendor: Option<String>,
+        model: Option<String>,
+        serial_number: Option<String>,
+        technology: Option<Named<BatteryTechnology>>,
+        state: Option<Named<BatteryState>>,
+        energy: Option<f32>,
+        energy_full: Option<f32>,
+        energy_full_design: Option<f32>,
+        energy_rate: Option<f32>,
+        voltage: Option<f32>,
+        temperature: Option<f32>,
+        cycle_count: Option<u32>,
+        present: Option<bool>,
+    ) {
+        let mut fake = self.lock();
+        if vendor.is_some() {
+            fake.vendor = vendor;
+        }
+        if model.is_some() {
+            fake.model = model;
+        }
+        if serial_number.is_some() {
+            fake.serial_number = serial_number;
+        }
+        if let Some(technology) = technology {
+            fake.technology = technology.resolve(parse_technology);
+        }
+        if let Some(state) = state {
+            fake.state = state.resolve(parse_state);
+        }
+        if let Some(energy) = energy {
+            fake.energy = energy;
+        }
+        if let Some(energy_full) = energy_full {
+            fake.energy_full = energy_full;
+        }
+        if let Some(energy_full_design) = energy_full_design {
+            fake.energy_full_design = energy_full_design;
+        }
+        if let Some(energy_rate) = energy_rate {
+            fake.energy_rate = energy_rate;
+        }
+        if let Some(voltage) = voltage {
+            fake.voltage = voltage;
+        }
+        if temperature.is_some() {
+            fake.temperature = temperature;
+        }
+        if cycle_count.is_some() {
+            fake.cycle_count = cycle_count;
+        }
+        if let Some(present) = present {
+            fake.present = present;
+        }
+    }
+
+    /// Advances the virtual battery by the given number of seconds at its current energy rate.
+    ///
+    /// A discharging battery loses energy and becomes `Empty` when it runs out. A charging
+    /// battery gains energy and becomes `Full` when it reaches its full energy.
+    ///
+    /// # Arguments
+    ///
+    /// * `seconds` - The number of seconds to advance.
+    /// * `steps` - How many times to advance by `seconds` (default: 1).
+    #[pyo3(signature = (seconds, steps=1))]
+    fn step(&self, seconds: f32, steps: u32) {
+        let mut fake = self.lock();
+        for _ in 0..steps {
+            fake.step(seconds);
+        }
+    }
+
+    /// Returns the current energy in watt-hours.
+    #[getter]
+    fn energy(&self) -> PyResult<f32> {
+        Ok(self.lock().energy)
+    }
+
+    /// Returns the percentage of the battery that is full.
+    #[getter]
+    fn percent(&self) -> PyResult<f32> {
+        Ok(self.lock().reading().state_of_charge)
+    }
+
+    /// Returns the state of the battery.
+    #[getter]
+    fn state(&self) -> PyResult<BatteryState> {
+        Ok(self.lock().state)
+    }
+
+    /// Returns whether the battery is present.
+    #[getter]
+    fn present(&self) -> PyResult<bool> {
+        Ok(self.lock().present)
+    }
+
+    /// Returns a string representation of the virtual battery.
+    fn __repr__(&self) -> PyResult<String> {
+        let fake = self.lock();
+        Ok(format!(
+            "FakeBattery(state={:?}, energy={:.1} Wh, energy_full={:.1} Wh, energy_rate={:.1} W)",
+            fake.state, fake.energy, fake.energy_full, fake.energy_rate
+        ))
+    }
+}
+
+/// Adds the `batteryinfo.testing` classes to a module.
+pub fn register(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<FakeBattery>()?;
+    Ok(())
+}
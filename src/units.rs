@@ -0,0 +1,183 @@
+//! Units of measurement and conversions between them.
+//!
+//! Each quantity has a base unit, the one used by `Reading`: Wh for energy, W for power, V for
+//! voltage, °C for temperature and % for ratios. Every other unit is defined by its size in the
+//! base unit and, for temperatures, the base-unit value of its zero.
+
+use crate::error::{Error, Result};
+
+/// A physical quantity measured by a unit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quantity {
+    /// Energy, in Wh.
+    Energy,
+    /// Power, in W.
+    Power,
+    /// Electric potential, in V.
+    Voltage,
+    /// Temperature, in °C.
+    Temperature,
+    /// A ratio, in %.
+    Ratio,
+}
+
+impl Quantity {
+    /// Returns the symbol of the base unit of the quantity.
+    pub fn base(self) -> &'static str {
+        match self {
+            Quantity::Energy => "Wh",
+            Quantity::Power => "W",
+            Quantity::Voltage => "V",
+            Quantity::Temperature => "°C",
+            Quantity::Ratio => "%",
+        }
+    }
+}
+
+/// A unit of measurement.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Unit {
+    /// The symbol of the unit, e.g. `Wh`.
+    pub symbol: &'static str,
+    /// The quantity the unit measures.
+    pub quantity: Quantity,
+    /// The size of the unit in the base unit.
+    scale: f64,
+    /// The value of zero in this unit, in the base unit.
+    offset: f64,
+}
+
+/// Defines a unit that shares its zero with the base unit.
+const fn unit(symbol: &'static str, quantity: Quantity, scale: f64) -> Unit {
+    Unit {
+        symbol,
+        quantity,
+        scale,
+        offset: 0.0,
+    }
+}
+
+/// The known units.
+const UNITS: &[Unit] = &[
+    unit("Wh", Quantity::Energy, 1.0),
+    unit("mWh", Quantity::Energy, 1e-3),
+    unit("kWh", Quantity::Energy, 1e3),
+    unit("J", Quantity::Energy, 1.0 / 3600.0),
+    unit("kJ", Quantity::Energy, 1e3 / 3600.0),
+    unit("W", Quantity::Power, 1.0),
+    unit("mW", Quantity::Power, 1e-3),
+    unit("kW", Quantity::Power, 1e3),
+    unit("V", Quantity::Voltage, 1.0),
+    unit("mV", Quantity::Voltage, 1e-3),
+    unit("°C", Quantity::Temperature, 1.0),
+    Unit {
+        symbol: "°F",
+        quantity: Quantity::Temperature,
+        scale: 5.0 / 9.0,
+        offset: -32.0 * 5.0 / 9.0,
+    },
+    Unit {
+        symbol: "K",
+        quantity: Quantity::Temperature,
+        scale: 1.0,
+        offset: -273.15,
+    },
+    unit("%", Quantity::Ratio, 1.0),
+];
+
+/// Other spellings accepted for unit symbols.
+const ALIASES: &[(&str, &str)] = &[("degC", "°C"), ("C", "°C"), ("degF", "°F"), ("F", "°F")];
+
+impl Unit {
+    /// Looks up a unit by its symbol or an alias of it.
+    pub fn find(symbol: &str) -> Result<Unit> {
+        let symbol = ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == symbol)
+            .map_or(symbol, |(_, symbol)| symbol);
+        UNITS
+            .iter()
+            .find(|unit| unit.symbol == symbol)
+            .copied()
+            .ok_or_else(|| Error::UnknownUnit(symbol.to_string()))
+    }
+}
+
+/// Converts a value between two units of the same quantity.
+///
+/// A value is always convertible to its own units, even if they are not known.
+pub fn convert(value: f64, from: &str, to: &str) -> Result<f64> {
+    if from == to {
+        return Ok(value);
+    }
+    let (from_unit, to_unit) = (Unit::find(from)?, Unit::find(to)?);
+    if from_unit.quantity != to_unit.quantity {
+        return Err(Error::IncompatibleUnits(from.to_string(), to.to_string()));
+    }
+    let base = value * from_unit.scale + from_unit.offset;
+    Ok((base - to_unit.offset) / to_unit.scale)
+}
+
+/// Widens a value read as an `f32` to an `f64` through its shortest decimal form, so that e.g.
+/// 88.2 stays 88.2 rather than becoming the nearest f64 to the f32 value, 88.19999694824219.
+pub fn widen(value: f32) -> f64 {
+    value.to_string().parse().unwrap_or(value as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Asserts that two values are equal to within rounding.
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn converts_between_units() {
+        assert_close(convert(3600.0, "J", "Wh").unwrap(), 1.0);
+        assert_close(convert(1.0, "Wh", "kJ").unwrap(), 3.6);
+        assert_close(convert(1500.0, "mW", "W").unwrap(), 1.5);
+        assert_close(convert(212.0, "°F", "°C").unwrap(), 100.0);
+        assert_close(convert(0.0, "K", "°F").unwrap(), -459.67);
+        assert_close(convert(20.0, "degC", "K").unwrap(), 293.15);
+    }
+
+    #[test]
+    fn round_trips_every_unit() {
+        for from in UNITS {
+            for to in UNITS.iter().filter(|to| to.quantity == from.quantity) {
+                let there = convert(42.5, from.symbol, to.symbol).unwrap();
+                assert_close(convert(there, to.symbol, from.symbol).unwrap(), 42.5);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_incompatible_and_unknown_units() {
+        assert!(matches!(
+            convert(1.0, "Wh", "W"),
+            Err(Error::IncompatibleUnits(..))
+        ));
+        assert!(matches!(
+            convert(1.0, "%", "°C"),
+            Err(Error::IncompatibleUnits(..))
+        ));
+        assert!(matches!(
+            convert(1.0, "Wh", "Ah"),
+            Err(Error::UnknownUnit(_))
+        ));
+        assert_eq!(convert(1.5, "Ah", "Ah").unwrap(), 1.5);
+    }
+
+    #[test]
+    fn widens_to_the_shortest_decimal() {
+        assert_eq!(widen(88.2), 88.2);
+        assert_eq!(widen(12.345), 12.345);
+    }
+}